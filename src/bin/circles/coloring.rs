@@ -0,0 +1,146 @@
+//! How packed circles get their colours.
+
+use std::path::PathBuf;
+
+use nannou::{
+    prelude::*,
+    rand::{prelude::StdRng, seq::SliceRandom},
+};
+use nannou_playground::palette::Palette;
+use serde::{Deserialize, Serialize};
+
+/// The colouring strategy, as saved in presets. All but `Image` take their colours from the
+/// palette.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Coloring {
+    /// A random palette colour for every circle.
+    #[default]
+    Random,
+    /// One palette colour per radius class, in palette order from the biggest class down.
+    Radius,
+    /// The palette spread across the region along `angle` (in degrees, 90 is bottom to top).
+    Gradient {
+        #[serde(default = "default_angle")]
+        angle: f32,
+    },
+    /// The colour of an image stretched over the window, at each circle's centre.
+    Image {
+        path: PathBuf,
+        /// Use the closest palette colour instead of the exact colour.
+        #[serde(default)]
+        snap: bool,
+    },
+    /// Random palette colours, but never the same for touching circles.
+    Graph,
+}
+
+fn default_angle() -> f32 {
+    90.0
+}
+
+impl Coloring {
+    /// The strategy after this one, for cycling through them with a key. `Image` needs a path
+    /// so it can only be chosen in presets.
+    pub fn next(&self) -> Coloring {
+        match self {
+            Coloring::Random => Coloring::Radius,
+            Coloring::Radius => Coloring::Gradient {
+                angle: default_angle(),
+            },
+            Coloring::Gradient { .. } => Coloring::Graph,
+            Coloring::Image { .. } | Coloring::Graph => Coloring::Random,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coloring::Random => "random",
+            Coloring::Radius => "radius",
+            Coloring::Gradient { .. } => "gradient",
+            Coloring::Image { .. } => "image",
+            Coloring::Graph => "graph",
+        }
+    }
+}
+
+/// The palette colour closest to `color`.
+pub fn nearest(palette: &Palette, color: Rgb) -> Rgb {
+    let distance = |c: &Rgb| {
+        vec3(c.red, c.green, c.blue).distance_squared(vec3(color.red, color.green, color.blue))
+    };
+    *palette
+        .colors()
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .unwrap()
+}
+
+/// Give every node a palette index different from its `neighbours`, visiting the most
+/// connected nodes first and picking randomly among the free colours. Only if a node has at
+/// least as many neighbours as there are colours can it end up sharing one, with as few
+/// neighbours as possible.
+pub fn graph(neighbours: &[Vec<usize>], colors: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..neighbours.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(neighbours[i].len()));
+    let mut assigned: Vec<Option<usize>> = vec![None; neighbours.len()];
+    let mut uses = vec![0; colors];
+    for i in order {
+        uses.iter_mut().for_each(|u| *u = 0);
+        for &j in &neighbours[i] {
+            if let Some(c) = assigned[j] {
+                uses[c] += 1;
+            }
+        }
+        let fewest = *uses.iter().min().unwrap();
+        let candidates: Vec<usize> = (0..colors).filter(|&c| uses[c] == fewest).collect();
+        assigned[i] = candidates.choose(rng).copied();
+    }
+    assigned.into_iter().map(Option::unwrap).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::rand::SeedableRng;
+
+    use super::*;
+
+    /// How many neighbours of each node share its colour.
+    fn clashes(neighbours: &[Vec<usize>], colors: &[usize]) -> Vec<usize> {
+        neighbours
+            .iter()
+            .enumerate()
+            .map(|(i, ns)| ns.iter().filter(|&&j| colors[j] == colors[i]).count())
+            .collect()
+    }
+
+    #[test]
+    fn neighbours_get_different_colours() {
+        // Two triangles sharing node 0, which has more neighbours than there are colours
+        let neighbours = vec![
+            vec![1, 2, 3, 4],
+            vec![0, 2],
+            vec![0, 1],
+            vec![0, 4],
+            vec![0, 3],
+        ];
+        for seed in 0..20 {
+            let colors = graph(&neighbours, 3, &mut StdRng::seed_from_u64(seed));
+            assert!(colors.iter().all(|&c| c < 3));
+            assert_eq!(clashes(&neighbours, &colors), [0; 5], "{:?}", colors);
+        }
+    }
+
+    #[test]
+    fn too_few_colours_clash_as_little_as_possible() {
+        // Four nodes all touching each other, so one pair has to share a colour
+        let neighbours: Vec<Vec<usize>> = (0..4)
+            .map(|i| (0..4).filter(|&j| j != i).collect())
+            .collect();
+        for seed in 0..20 {
+            let colors = graph(&neighbours, 3, &mut StdRng::seed_from_u64(seed));
+            let clashes = clashes(&neighbours, &colors);
+            assert_eq!(clashes.iter().sum::<usize>(), 2, "{:?}", colors);
+        }
+    }
+}
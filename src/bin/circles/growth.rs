@@ -0,0 +1,247 @@
+//! Growing packed circles until they fill the gaps, one frame at a time.
+
+use nannou::prelude::*;
+use nannou_playground::grid::Grid;
+use serde::{Deserialize, Serialize};
+
+use crate::{region::Area, Circle};
+
+/// How circles grow, as saved in presets.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Growth {
+    /// Start growing as soon as the circles are packed, instead of waiting for `G`.
+    #[serde(default)]
+    pub auto: bool,
+    /// How much the radius grows each frame.
+    #[serde(default = "default_rate")]
+    pub rate: f32,
+    /// Passes over all overlapping pairs each frame. More passes settle faster but cost more.
+    #[serde(default = "default_iterations")]
+    pub iterations: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_radius: Option<f32>,
+}
+
+fn default_rate() -> f32 {
+    0.25
+}
+
+fn default_iterations() -> u32 {
+    4
+}
+
+impl Default for Growth {
+    fn default() -> Self {
+        Growth {
+            auto: false,
+            rate: default_rate(),
+            iterations: default_iterations(),
+            max_radius: None,
+        }
+    }
+}
+
+/// Overlap left after resolving collisions that makes circles shrink back.
+const JAMMED: f32 = 0.05;
+/// Frames in a row a circle can barely grow before it stops growing for good.
+const STALLS: u32 = 30;
+/// Movement below which circles count as settled.
+const SETTLED: f32 = 0.01;
+
+/// All circles grow at once and overlapping circles push each other apart, big circles
+/// pushing harder. Circles that can't be pushed apart shrink back, and stop growing once
+/// they've been stuck for a while.
+pub struct Relaxation {
+    /// Radii before growing, which circles never shrink below.
+    start: Vec<f32>,
+    /// Frames each circle has barely grown in a row.
+    stalls: Vec<u32>,
+    frames: u32,
+}
+
+impl Relaxation {
+    pub fn new(circles: &[Circle]) -> Self {
+        Relaxation {
+            start: circles.iter().map(|c| c.r).collect(),
+            stalls: vec![0; circles.len()],
+            frames: 0,
+        }
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Advance by one frame, keeping circles at least `gap` apart and inside `area`. Returns
+    /// whether anything changed, i.e. false once the packing has settled.
+    pub fn step(
+        &mut self,
+        circles: &mut [Circle],
+        grid: &mut Grid,
+        area: &Area,
+        gap: f32,
+        growth: &Growth,
+    ) -> bool {
+        self.frames += 1;
+        let max_radius = growth.max_radius.unwrap_or(f32::INFINITY);
+        let mut growing = vec![false; circles.len()];
+        let before: Vec<f32> = circles.iter().map(|c| c.r).collect();
+        for (i, c) in circles.iter_mut().enumerate() {
+            if self.stalls[i] >= STALLS || c.r >= max_radius {
+                continue;
+            }
+            let r = (c.r + growth.rate).min(max_radius);
+            // Circles growing into the edge of a rectangle slide away from it, even without
+            // relaxation passes to push them
+            let o = constrain(area, c.o, r);
+            if area.contains(o, r) {
+                c.o = o;
+                c.r = r;
+                growing[i] = true;
+            } else {
+                self.stalls[i] = STALLS;
+            }
+        }
+
+        let mut moved = 0.0;
+        let mut overlap = vec![0.0; circles.len()];
+        for _ in 0..growth.iterations {
+            rebuild(grid, circles);
+            let pushes = resolve(circles, grid, gap, &mut overlap);
+            for (c, push) in circles.iter_mut().zip(pushes) {
+                let o = constrain(area, c.o + push, c.r);
+                if area.contains(o, c.r) {
+                    moved = f32::max(moved, o.distance(c.o));
+                    c.o = o;
+                }
+            }
+        }
+        rebuild(grid, circles);
+        resolve(circles, grid, gap, &mut overlap);
+        let mut grew = false;
+        for (i, c) in circles.iter_mut().enumerate() {
+            if overlap[i] > JAMMED {
+                // Both circles of the pair give up half, so they end up just touching
+                c.r = (c.r - overlap[i] / 2.0).max(self.start[i]);
+            }
+            if !growing[i] {
+                continue;
+            }
+            if c.r - before[i] < growth.rate * 0.25 {
+                self.stalls[i] += 1;
+            } else {
+                self.stalls[i] = 0;
+                grew = true;
+            }
+        }
+        rebuild(grid, circles);
+        grew || moved > SETTLED
+    }
+}
+
+fn rebuild(grid: &mut Grid, circles: &[Circle]) {
+    grid.clear();
+    for (i, c) in circles.iter().enumerate() {
+        grid.insert(i, c.bbox());
+    }
+}
+
+/// How far each circle should move to get out of the circles it overlaps, and the largest
+/// overlap of each circle.
+fn resolve(circles: &[Circle], grid: &Grid, gap: f32, overlap: &mut [f32]) -> Vec<Vec2> {
+    let mut pushes = vec![Vec2::ZERO; circles.len()];
+    // Grid queries report circles once per cell, so remember which were seen for which circle
+    let mut seen = vec![usize::MAX; circles.len()];
+    overlap.iter_mut().for_each(|o| *o = 0.0);
+    for (i, a) in circles.iter().enumerate() {
+        for j in grid.query(a.bbox().pad(-gap)) {
+            if j <= i || seen[j] == i {
+                continue;
+            }
+            seen[j] = i;
+            let b = &circles[j];
+            let d = a.o.distance(b.o);
+            let depth = a.r + b.r + gap - d;
+            if depth <= 0.0 {
+                continue;
+            }
+            overlap[i] = f32::max(overlap[i], depth);
+            overlap[j] = f32::max(overlap[j], depth);
+            let dir = if d > 0.0 { (a.o - b.o) / d } else { Vec2::X };
+            // Share the correction by area, so small circles give way to big ones
+            let (ma, mb) = (a.r * a.r, b.r * b.r);
+            pushes[i] += dir * depth * mb / (ma + mb);
+            pushes[j] -= dir * depth * ma / (ma + mb);
+        }
+    }
+    pushes
+}
+
+/// Keep circles inside rectangular areas by sliding them back in. Other areas reject moves
+/// that would leave them instead.
+fn constrain(area: &Area, o: Vec2, r: f32) -> Vec2 {
+    match area {
+        Area::Rect(rect) => {
+            let inner = rect.pad(r.min(rect.w() / 2.0).min(rect.h() / 2.0));
+            vec2(
+                o.x.clamp(inner.left(), inner.right()),
+                o.y.clamp(inner.bottom(), inner.top()),
+            )
+        }
+        _ => o,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, y: f32, r: f32) -> Circle {
+        Circle {
+            o: vec2(x, y),
+            r,
+            class: 0,
+            pick: Rgb::new(0.0, 0.0, 0.0),
+            c: Rgba::new(0.0, 0.0, 0.0, 1.0),
+        }
+    }
+
+    /// Run the relaxation until it settles, or for at most `frames` frames.
+    fn relax(circles: &mut [Circle], area: &Area, growth: &Growth, frames: u32) {
+        let mut grid = Grid::new(area.bbox(), 10.0);
+        let mut relaxation = Relaxation::new(circles);
+        while relaxation.frames() < frames && relaxation.step(circles, &mut grid, area, 1.0, growth)
+        {
+        }
+    }
+
+    #[test]
+    fn circles_grow_inside_rectangles_without_relaxing() {
+        let area = Area::Rect(Rect::from_w_h(100.0, 100.0));
+        let mut circles = [circle(45.0, 0.0, 4.0), circle(-30.0, -30.0, 2.0)];
+        let growth = Growth {
+            iterations: 0,
+            max_radius: Some(20.0),
+            ..Growth::default()
+        };
+        relax(&mut circles, &area, &growth, 200);
+        for c in &circles {
+            assert_eq!(c.r, 20.0);
+            assert!(area.contains(c.o, c.r), "{} sticks out at {}", c.r, c.o);
+        }
+    }
+
+    #[test]
+    fn overlapping_circles_push_each_other_apart() {
+        let area = Area::Rect(Rect::from_w_h(100.0, 100.0));
+        let mut circles = [circle(-1.0, 0.0, 10.0), circle(1.0, 0.0, 10.0)];
+        let growth = Growth {
+            rate: 0.0,
+            ..Growth::default()
+        };
+        relax(&mut circles, &area, &growth, 10);
+        assert!(circles[0].o.distance(circles[1].o) >= 21.0 - 1e-3);
+        assert!((circles[0].o + circles[1].o).length() < 1e-3);
+        assert!(circles.iter().all(|c| c.r == 10.0));
+    }
+}
@@ -0,0 +1,478 @@
+use coloring::Coloring;
+use growth::{Growth, Relaxation};
+use nannou::{
+    prelude::*,
+    rand::{
+        prelude::{SeedableRng, StdRng},
+        Rng,
+    },
+};
+use nannou_egui::egui;
+use nannou_playground::{
+    grid::Grid, image_map::ImageMap, palette::Palette, Canvas, FrameInfo, Sketch,
+};
+use region::{Area, Region};
+use schedule::{ClassStats, Schedule};
+use serde::{Deserialize, Serialize};
+
+mod coloring;
+mod growth;
+mod region;
+mod schedule;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 800;
+const DEFAULT_PALETTE: &str = "tango";
+/// How far apart circles can be and still count as touching.
+const TOUCHING: f32 = 0.5;
+
+fn main() {
+    nannou_playground::run::<Model>();
+}
+
+struct Circle {
+    o: Vec2,
+    r: f32,
+    /// Index of the radius class the circle was packed in.
+    class: usize,
+    /// Palette colour picked at random when packing.
+    pick: Rgb,
+    c: Rgba,
+}
+
+impl Circle {
+    fn bbox(&self) -> Rect {
+        Rect::from_xy_wh(self.o, Vec2::splat(self.r * 2.0))
+    }
+}
+
+struct Model {
+    seed: u64,
+    region: Region,
+    schedule: Schedule,
+    /// Minimum space between circles.
+    gap: f32,
+    growth: Growth,
+    /// The growth simulation, while it's running.
+    relaxation: Option<Relaxation>,
+    /// Where circles are allowed to go.
+    area: Area,
+    circles: Vec<Circle>,
+    /// Index of `circles`, to only check nearby circles for collisions.
+    grid: Grid,
+    palette: Palette,
+    coloring: Coloring,
+    /// The image of `Coloring::Image`, loaded along with the colouring.
+    image: Option<ImageMap>,
+    /// What the last packing achieved, per radius class.
+    stats: Vec<ClassStats>,
+    /// Size of the packed region, to turn `stats` into coverage.
+    region_size: f32,
+}
+
+impl Model {
+    /// Pack the region from scratch.
+    fn repack(&mut self) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        self.circles.clear();
+        self.grid.clear();
+        self.stats.clear();
+        let window = Rect::from_w_h(WIDTH as f32, HEIGHT as f32);
+        let area = match self.region.area(window, self.schedule.max_radius()) {
+            Ok(area) => area,
+            Err(e) => {
+                println!(
+                    "Couldn't load the region, packing the window instead: {}",
+                    e
+                );
+                Area::Rect(window)
+            }
+        };
+        self.region_size = area.size();
+        match self.schedule.clone() {
+            Schedule::Classes { mut classes } => {
+                // Big to small, whatever order the preset lists them in
+                classes.sort_by(|a, b| b.radius.total_cmp(&a.radius));
+                for (i, class) in classes.into_iter().enumerate() {
+                    let mut stats = ClassStats {
+                        radius: class.radius,
+                        circles: 0,
+                        area: 0.0,
+                    };
+                    let mut failures = 0;
+                    while failures < class.attempts && stats.circles < class.count {
+                        if self.place_circle(&mut rng, &area, class.radius, i) {
+                            stats.circles += 1;
+                            stats.area += PI * class.radius * class.radius;
+                        } else {
+                            failures += 1;
+                        }
+                    }
+                    self.stats.push(stats);
+                }
+            }
+            Schedule::PowerLaw {
+                max_radius,
+                min_radius,
+                exponent,
+                attempts,
+            } => {
+                for n in 0..schedule::MAX_RADII {
+                    let r = max_radius * (n as f32 + 1.0).powf(-exponent);
+                    if r < min_radius {
+                        break;
+                    }
+                    // Bin by halving radii, so the readout stays short
+                    let class = (max_radius / r).log2().floor() as usize;
+                    if !(0..attempts).any(|_| self.place_circle(&mut rng, &area, r, class)) {
+                        continue;
+                    }
+                    while self.stats.len() <= class {
+                        let radius = max_radius / 2.0.powi(self.stats.len() as i32);
+                        self.stats.push(ClassStats {
+                            radius,
+                            circles: 0,
+                            area: 0.0,
+                        });
+                    }
+                    self.stats[class].circles += 1;
+                    self.stats[class].area += PI * r * r;
+                }
+            }
+        }
+        self.area = area;
+        self.relaxation = self.growth.auto.then(|| Relaxation::new(&self.circles));
+        self.recolor();
+    }
+
+    /// Try one random position for a circle of radius `r`, returning whether it fit.
+    fn place_circle(&mut self, rng: &mut StdRng, area: &Area, r: f32, class: usize) -> bool {
+        let bounds = area.bbox();
+        if bounds.w() <= 2.0 * r || bounds.h() <= 2.0 * r {
+            return false;
+        }
+        let x = rng.gen_range(bounds.left() + r..bounds.right() - r);
+        let y = rng.gen_range(bounds.bottom() + r..bounds.top() - r);
+        let o = vec2(x, y);
+        if !area.contains(o, r) {
+            return false;
+        }
+        let reach = Rect::from_xy_wh(o, Vec2::splat((r + self.gap) * 2.0));
+        if self.grid.query(reach).any(|j| {
+            let c = &self.circles[j];
+            o.distance(c.o) < r + c.r + self.gap
+        }) {
+            // we intersect another circle..
+            return false;
+        }
+        let pick = self.palette.choose(rng);
+        let circle = Circle {
+            o,
+            r,
+            class,
+            pick,
+            c: pick.into(),
+        };
+        self.grid.insert(self.circles.len(), circle.bbox());
+        self.circles.push(circle);
+        true
+    }
+
+    /// Start growing the circles, or stop if they're already growing.
+    fn toggle_growth(&mut self) {
+        if self.relaxation.take().is_some() {
+            self.settle();
+        } else {
+            self.relaxation = Some(Relaxation::new(&self.circles));
+        }
+    }
+
+    /// Catch up with the circles' new sizes and positions after growing.
+    fn settle(&mut self) {
+        self.stats.iter_mut().for_each(|s| s.area = 0.0);
+        for c in &self.circles {
+            self.stats[c.class].area += PI * c.r * c.r;
+        }
+        self.recolor();
+    }
+
+    /// Apply the colouring strategy to all circles.
+    fn recolor(&mut self) {
+        let colors: Vec<Rgb> = match &self.coloring {
+            Coloring::Random => self.circles.iter().map(|c| c.pick).collect(),
+            Coloring::Radius => {
+                let palette = self.palette.colors();
+                let class = |c: &Circle| palette[c.class % palette.len()];
+                self.circles.iter().map(class).collect()
+            }
+            Coloring::Gradient { angle } => {
+                let dir = vec2(angle.to_radians().cos(), angle.to_radians().sin());
+                let bounds = self.area.bbox();
+                let extent = (bounds.wh() * dir).abs().dot(Vec2::ONE);
+                let t = |c: &Circle| (c.o - bounds.xy()).dot(dir) / extent + 0.5;
+                let color = |c| self.palette.gradient(t(c));
+                self.circles.iter().map(color).collect()
+            }
+            Coloring::Image { snap, .. } => match &self.image {
+                Some(image) => {
+                    let color = |c: &Circle| {
+                        let color: Rgb = image.sample(c.o).into_format::<f32, f32>().color;
+                        if *snap {
+                            coloring::nearest(&self.palette, color)
+                        } else {
+                            color
+                        }
+                    };
+                    self.circles.iter().map(color).collect()
+                }
+                // The image couldn't be loaded
+                None => self.circles.iter().map(|c| c.pick).collect(),
+            },
+            Coloring::Graph => {
+                let neighbours: Vec<_> = (0..self.circles.len())
+                    .map(|i| self.neighbours(i))
+                    .collect();
+                let mut rng = StdRng::seed_from_u64(self.seed);
+                let palette = self.palette.colors();
+                coloring::graph(&neighbours, palette.len(), &mut rng)
+                    .into_iter()
+                    .map(|i| palette[i])
+                    .collect()
+            }
+        };
+        for (circle, color) in self.circles.iter_mut().zip(colors) {
+            circle.c = color.into();
+        }
+    }
+
+    /// The circles touching circle `i`, i.e. those no further than the minimum gap.
+    fn neighbours(&self, i: usize) -> Vec<usize> {
+        let mut touching: Vec<usize> = self
+            .gaps(i, self.gap + TOUCHING)
+            .filter(|&(_, gap)| gap <= self.gap + TOUCHING)
+            .map(|(j, _)| j)
+            .collect();
+        touching.sort_unstable();
+        touching.dedup();
+        touching
+    }
+
+    /// Gaps between circle `i` and other circles, including all that are at most `search`
+    /// away, and maybe some further ones. Circles can be reported more than once.
+    fn gaps(&self, i: usize, search: f32) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let c = &self.circles[i];
+        let bbox = Rect::from_xy_wh(c.o, Vec2::splat((c.r + search) * 2.0));
+        self.grid
+            .query(bbox)
+            .filter(move |&j| j != i)
+            .map(move |j| {
+                let c1 = &self.circles[j];
+                (j, c.o.distance(c1.o) - c.r - c1.r)
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Params {
+    #[serde(default)]
+    gap: f32,
+    #[serde(default = "default_palette")]
+    palette: String,
+    #[serde(default)]
+    region: Region,
+    #[serde(default)]
+    schedule: Schedule,
+    #[serde(default)]
+    coloring: Coloring,
+    #[serde(default)]
+    growth: Growth,
+}
+
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
+}
+
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [WIDTH, HEIGHT];
+
+    type Params = Params;
+
+    fn new() -> Self {
+        Model {
+            seed: 0,
+            region: Region::Window,
+            schedule: Schedule::default(),
+            gap: 0.0,
+            growth: Growth::default(),
+            relaxation: None,
+            area: Area::Rect(Rect::from_w_h(WIDTH as f32, HEIGHT as f32)),
+            circles: vec![],
+            grid: Grid::new(Rect::from_w_h(WIDTH as f32, HEIGHT as f32), 8.0),
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
+            coloring: Coloring::default(),
+            image: None,
+            stats: vec![],
+            region_size: 0.0,
+        }
+    }
+
+    fn params(&self) -> Params {
+        Params {
+            gap: self.gap,
+            palette: self.palette.name().to_string(),
+            region: self.region.clone(),
+            schedule: self.schedule.clone(),
+            coloring: self.coloring.clone(),
+            growth: self.growth.clone(),
+        }
+    }
+
+    fn set_params(&mut self, params: Params) {
+        self.region = params.region;
+        self.schedule = params.schedule;
+        self.coloring = params.coloring;
+        self.image = match &self.coloring {
+            Coloring::Image { path, .. } => {
+                let window = Rect::from_w_h(WIDTH as f32, HEIGHT as f32);
+                match ImageMap::open(path, window) {
+                    Ok(image) => Some(image),
+                    Err(e) => {
+                        println!(
+                            "Couldn't load the colouring image, colouring randomly: {}",
+                            e
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        self.growth = params.growth;
+        self.gap = params.gap;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+    }
+
+    fn key_pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::G => self.toggle_growth(),
+            Key::C => {
+                self.coloring = self.coloring.next();
+                println!("Colouring by {}", self.coloring.name());
+                self.recolor();
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn reset(&mut self) {
+        self.repack();
+    }
+
+    fn ui(&mut self, ctx: &egui::Context) -> bool {
+        egui::Window::new("Coverage").show(ctx, |ui| {
+            let coverage = |area: f32| format!("{:.1}%", area / self.region_size * 100.0);
+            egui::Grid::new("stats").striped(true).show(ui, |ui| {
+                ui.label("Radius");
+                ui.label("Circles");
+                ui.label("Coverage");
+                ui.end_row();
+                for class in &self.stats {
+                    ui.label(format!("{}", class.radius));
+                    ui.label(class.circles.to_string());
+                    ui.label(coverage(class.area));
+                    ui.end_row();
+                }
+                ui.label("Total");
+                ui.label(
+                    self.stats
+                        .iter()
+                        .map(|c| c.circles)
+                        .sum::<u32>()
+                        .to_string(),
+                );
+                ui.label(coverage(self.stats.iter().map(|c| c.area).sum()));
+                ui.end_row();
+            });
+        });
+        false
+    }
+
+    fn update(&mut self, _info: &FrameInfo) {
+        let Some(relaxation) = &mut self.relaxation else {
+            return;
+        };
+        let moving = relaxation.step(
+            &mut self.circles,
+            &mut self.grid,
+            &self.area,
+            self.gap,
+            &self.growth,
+        );
+        if !moving {
+            println!("Settled after {} frames", relaxation.frames());
+            self.relaxation = None;
+            self.settle();
+        }
+    }
+
+    fn draw(&self, canvas: &Canvas, _info: &FrameInfo) {
+        let draw = canvas.scale(0.95);
+
+        for circle in &self.circles {
+            draw.ellipse(circle.o, Vec2::splat(circle.r * 2.0), circle.c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou_playground::preset::Preset;
+
+    use super::*;
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
+
+    #[test]
+    fn classes_are_packed_from_big_to_small() {
+        let class = |radius, count| schedule::Class {
+            radius,
+            count,
+            attempts: 100,
+        };
+        let mut model = Model::new();
+        model.schedule = Schedule::Classes {
+            classes: vec![class(10.0, 5), class(30.0, 1)],
+        };
+        model.repack();
+        let radii: Vec<f32> = model.stats.iter().map(|s| s.radius).collect();
+        assert_eq!(radii, [30.0, 10.0]);
+        assert_eq!(model.circles[0].r, 30.0);
+        assert_eq!(model.stats[0].circles, 1);
+        assert_eq!(model.stats[1].circles, 5);
+    }
+}
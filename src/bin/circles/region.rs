@@ -0,0 +1,279 @@
+//! Where circles are allowed to go.
+
+use std::{error::Error, path::PathBuf};
+
+use nannou::prelude::*;
+use nannou_playground::{image_map::ImageMap, raster::Raster, Canvas};
+use serde::{Deserialize, Serialize};
+
+/// The area to pack, as saved in presets. Coordinates are in window units, with the origin in
+/// the middle and y pointing up.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Region {
+    /// The whole window.
+    #[default]
+    Window,
+    Polygon {
+        points: Vec<[f32; 2]>,
+    },
+    /// The outlines of some text, centered in the window.
+    Text {
+        text: String,
+        size: u32,
+    },
+    /// A grayscale image stretched over the window: circles only go where it's brighter than
+    /// `threshold`, and get smaller as it gets darker.
+    Mask {
+        path: PathBuf,
+        /// Use dark areas instead of bright ones.
+        #[serde(default)]
+        invert: bool,
+        #[serde(default = "default_threshold")]
+        threshold: f32,
+    },
+}
+
+fn default_threshold() -> f32 {
+    0.1
+}
+
+impl Region {
+    /// Prepare the region for packing circles of at most `max_radius` into `rect`. Fails if a
+    /// mask can't be loaded, or if the region leaves no room at all.
+    pub fn area(&self, rect: Rect, max_radius: f32) -> Result<Area, Box<dyn Error>> {
+        let area = match self {
+            Region::Window => Area::Rect(rect),
+            Region::Polygon { points } => {
+                let points: Vec<Vec2> = points.iter().map(|&p| p.into()).collect();
+                let bbox = Rect::from_corners(
+                    points.iter().copied().reduce(Vec2::min).unwrap_or_default(),
+                    points.iter().copied().reduce(Vec2::max).unwrap_or_default(),
+                );
+                Area::Polygon { points, bbox }
+            }
+            Region::Text { text, size } => {
+                let canvas = Canvas::new();
+                canvas.background(BLACK);
+                canvas.text(text, Vec2::ZERO, *size, WHITE);
+                let mut raster = Raster::new(rect.w() as u32, rect.h() as u32)?;
+                raster.render(&canvas);
+                Area::mask(
+                    ImageMap::from_raster(&raster, rect),
+                    false,
+                    0.5,
+                    // Glyphs are all-or-nothing, so don't vary the radius
+                    f32::INFINITY,
+                )
+            }
+            Region::Mask {
+                path,
+                invert,
+                threshold,
+            } => Area::mask(ImageMap::open(path, rect)?, *invert, *threshold, max_radius),
+        };
+        // Also keeps the coverage stats from dividing by zero
+        if area.size() <= 0.0 {
+            return Err("the region is empty".into());
+        }
+        Ok(area)
+    }
+}
+
+/// A `Region` ready to be tested against.
+pub enum Area {
+    Rect(Rect),
+    Polygon {
+        points: Vec<Vec2>,
+        bbox: Rect,
+    },
+    Mask {
+        image: ImageMap,
+        invert: bool,
+        threshold: f32,
+        /// Radius allowed where the mask is at full brightness.
+        max_radius: f32,
+        /// Bounds of the part of the mask above the threshold.
+        bbox: Rect,
+        /// Size of the part of the mask above the threshold.
+        size: f32,
+    },
+}
+
+impl Area {
+    fn mask(image: ImageMap, invert: bool, threshold: f32, max_radius: f32) -> Self {
+        let mut area = Area::Mask {
+            bbox: image.rect(),
+            image,
+            invert,
+            threshold,
+            max_radius,
+            size: 0.0,
+        };
+        // Candidates far from anything allowed would only use up the attempts
+        let rect = area.bbox();
+        let mut bbox: Option<Rect> = None;
+        let mut samples = 0;
+        for y in (rect.bottom() as i32..rect.top() as i32).step_by(2) {
+            for x in (rect.left() as i32..rect.right() as i32).step_by(2) {
+                let p = vec2(x as f32, y as f32);
+                if area.contains(p, 0.0) {
+                    samples += 1;
+                    let r = Rect::from_xy_wh(p, Vec2::splat(2.0));
+                    bbox = Some(bbox.map_or(r, |b| b.stretch_to(p)));
+                }
+            }
+        }
+        if let Area::Mask { bbox: b, size, .. } = &mut area {
+            if let Some(bbox) = bbox {
+                *b = bbox.pad(-2.0);
+            }
+            *size = samples as f32 * 4.0;
+        }
+        area
+    }
+
+    /// Where to look for places to put circles.
+    pub fn bbox(&self) -> Rect {
+        match self {
+            Area::Rect(rect) => *rect,
+            Area::Polygon { bbox, .. } => *bbox,
+            Area::Mask { bbox, .. } => *bbox,
+        }
+    }
+
+    /// How much room there is in total, for coverage stats.
+    pub fn size(&self) -> f32 {
+        match self {
+            Area::Rect(rect) => rect.w() * rect.h(),
+            Area::Polygon { points, .. } => {
+                let twice: f32 = (0..points.len())
+                    .map(|i| points[i].perp_dot(points[(i + 1) % points.len()]))
+                    .sum();
+                twice.abs() / 2.0
+            }
+            Area::Mask { size, .. } => *size,
+        }
+    }
+
+    /// Whether a circle at `o` with radius `r` lies inside the area.
+    pub fn contains(&self, o: Vec2, r: f32) -> bool {
+        match self {
+            Area::Rect(rect) => {
+                let x = (o.x - rect.left()).min(rect.right() - o.x);
+                let y = (o.y - rect.bottom()).min(rect.top() - o.y);
+                // With some slack for circles slid right up against the edge
+                x.min(y) >= r - 1e-3
+            }
+            Area::Polygon { points, .. } => {
+                inside_polygon(points, o) && distance_to_outline(points, o) >= r
+            }
+            Area::Mask {
+                image,
+                invert,
+                threshold,
+                max_radius,
+                ..
+            } => {
+                let value = |p: Vec2| {
+                    let v = image.luma(p);
+                    if *invert {
+                        1.0 - v
+                    } else {
+                        v
+                    }
+                };
+                let center = value(o);
+                if center < *threshold || r > center * max_radius {
+                    return false;
+                }
+                // Check around the edge and half way in, more densely for bigger circles
+                let n = (r * 0.5).clamp(8.0, 64.0) as usize;
+                (0..n).all(|i| {
+                    let angle = i as f32 / n as f32 * TAU;
+                    let dir = vec2(angle.cos(), angle.sin());
+                    value(o + dir * r) >= *threshold && value(o + dir * r * 0.5) >= *threshold
+                })
+            }
+        }
+    }
+}
+
+/// Even-odd rule, so self-intersecting polygons get holes.
+fn inside_polygon(points: &[Vec2], p: Vec2) -> bool {
+    let mut inside = false;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn distance_to_outline(points: &[Vec2], p: Vec2) -> f32 {
+    (0..points.len())
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            let t = ((p - a).dot(b - a) / (b - a).length_squared()).clamp(0.0, 1.0);
+            p.distance(a + (b - a) * t)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> Rect {
+        Rect::from_w_h(800.0, 800.0)
+    }
+
+    fn polygon(points: &[[f32; 2]]) -> Region {
+        Region::Polygon {
+            points: points.to_vec(),
+        }
+    }
+
+    #[test]
+    fn polygons_hold_circles_clear_of_their_outline() {
+        let square = [[-100.0, -100.0], [100.0, -100.0], [100.0, 100.0], [-100.0, 100.0]];
+        let area = polygon(&square).area(window(), 50.0).unwrap();
+        assert_eq!(area.size(), 40000.0);
+        assert_eq!(area.bbox(), Rect::from_w_h(200.0, 200.0));
+        assert!(area.contains(Vec2::ZERO, 99.0));
+        assert!(!area.contains(Vec2::ZERO, 101.0));
+        assert!(!area.contains(vec2(95.0, 0.0), 10.0));
+        assert!(!area.contains(vec2(150.0, 0.0), 1.0));
+    }
+
+    #[test]
+    fn self_intersecting_polygons_have_holes() {
+        // A square drawn twice in the same direction around the middle, then once more inside
+        let points = [
+            [-100.0, -100.0],
+            [100.0, -100.0],
+            [100.0, 100.0],
+            [-100.0, 100.0],
+            [-100.0, -100.0],
+            [-50.0, -50.0],
+            [50.0, -50.0],
+            [50.0, 50.0],
+            [-50.0, 50.0],
+            [-50.0, -50.0],
+        ];
+        let area = polygon(&points).area(window(), 50.0).unwrap();
+        assert!(!area.contains(Vec2::ZERO, 1.0));
+        assert!(area.contains(vec2(75.0, 0.0), 10.0));
+    }
+
+    #[test]
+    fn empty_regions_are_errors() {
+        assert!(polygon(&[]).area(window(), 50.0).is_err());
+        assert!(polygon(&[[0.0, 0.0], [100.0, 100.0]])
+            .area(window(), 50.0)
+            .is_err());
+        assert!(Region::Window.area(window(), 50.0).is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+//! Which circle sizes get packed, in what order.
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+/// The sizes to pack, as saved in presets. Circles are always packed from big to small, as
+/// small circles fill the gaps between the big ones.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Schedule {
+    /// Fixed radius classes, packed from the biggest radius down.
+    Classes { classes: Vec<Class> },
+    /// A radius that shrinks with every circle, following `max_radius * (n + 1)^-exponent` for
+    /// the `n`th circle, until it drops below `min_radius` or `MAX_RADII` circles have been
+    /// tried. Exponents around 0.5 give dense, space filling packings; bigger ones leave more
+    /// room.
+    PowerLaw {
+        max_radius: f32,
+        #[serde(deserialize_with = "positive")]
+        min_radius: f32,
+        #[serde(deserialize_with = "positive")]
+        exponent: f32,
+        /// How many positions to try for each circle before moving on to the next size.
+        attempts: u32,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Class {
+    pub radius: f32,
+    /// Maximum number of circles to place.
+    pub count: u32,
+    /// How many failed positions to allow before moving on to the next class.
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+}
+
+fn default_attempts() -> u32 {
+    20000
+}
+
+/// Most radii a power law schedule tries, in case it shrinks very slowly.
+pub const MAX_RADII: u32 = 1_000_000;
+
+/// Only numbers above zero, as the radius of power law schedules would never shrink below
+/// `min_radius` otherwise.
+fn positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let value = f32::deserialize(deserializer)?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(D::Error::custom(format!(
+            "expected a number above 0, got {}",
+            value
+        )))
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        let class = |radius, count| Class {
+            radius,
+            count,
+            attempts: default_attempts(),
+        };
+        Schedule::Classes {
+            classes: vec![
+                class(80.0, 1),
+                class(50.0, 10),
+                class(20.0, 50),
+                class(15.0, 20),
+                class(10.0, 100),
+                class(8.0, 300),
+                class(5.0, 800),
+                class(2.0, 5000),
+                class(1.0, 5000),
+            ],
+        }
+    }
+}
+
+impl Schedule {
+    /// The largest radius that will be packed.
+    pub fn max_radius(&self) -> f32 {
+        match self {
+            Schedule::Classes { classes } => classes.iter().map(|c| c.radius).fold(0.0, f32::max),
+            Schedule::PowerLaw { max_radius, .. } => *max_radius,
+        }
+    }
+}
+
+/// How much of the region one size of circles ended up covering.
+#[derive(Clone, Debug)]
+pub struct ClassStats {
+    /// The radius, or largest radius for power law schedules, which are binned by halving
+    /// radii.
+    pub radius: f32,
+    pub circles: u32,
+    /// Summed area of the circles.
+    pub area: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POWER_LAW: &str = "type = \"powerlaw\"\nmax_radius = 120.0\nmin_radius = 1.0\n\
+                             exponent = 0.5\nattempts = 2000\n";
+
+    #[test]
+    fn power_laws_need_positive_exponents_and_radii() {
+        let schedule: Schedule = toml::from_str(POWER_LAW).unwrap();
+        assert_eq!(schedule.max_radius(), 120.0);
+        for (from, to) in [
+            ("exponent = 0.5", "exponent = 0.0"),
+            ("exponent = 0.5", "exponent = -0.5"),
+            ("min_radius = 1.0", "min_radius = 0.0"),
+        ] {
+            assert!(toml::from_str::<Schedule>(&POWER_LAW.replace(from, to)).is_err());
+        }
+    }
+
+    #[test]
+    fn classes_default_their_attempts() {
+        let text = "type = \"classes\"\n\n[[classes]]\nradius = 10.0\ncount = 5\n\n\
+                    [[classes]]\nradius = 30.0\ncount = 1\nattempts = 10\n";
+        let schedule: Schedule = toml::from_str(text).unwrap();
+        assert_eq!(schedule.max_radius(), 30.0);
+        let Schedule::Classes { classes } = schedule else {
+            panic!("expected classes");
+        };
+        assert_eq!(classes[0].attempts, default_attempts());
+        assert_eq!(classes[1].attempts, 10);
+        assert_eq!(Schedule::default().max_radius(), 80.0);
+    }
+}
@@ -0,0 +1,492 @@
+//! Chaotic maps whose orbits trace out strange attractors.
+
+use std::ops::RangeInclusive;
+
+use nannou::{prelude::*, rand::Rng};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+/// A map from the plane to itself, iterated to draw its attractor.
+pub trait Attractor: Sync {
+    /// The next point of an orbit. `r` is a uniform random number in `0..1`, for maps that
+    /// choose between several transformations.
+    fn step(&self, p: Vec2, r: f32) -> Vec2;
+
+    /// The tunable parameters, in the order `set_param` expects.
+    fn params(&self) -> Vec<Param>;
+
+    fn set_param(&mut self, i: usize, value: f32);
+}
+
+/// A parameter of an attractor, and the range it is randomized in.
+pub struct Param {
+    pub name: String,
+    pub value: f32,
+    pub range: RangeInclusive<f32>,
+    /// Random values are at least this far from 0, for parameters that are divided by.
+    gap: f32,
+}
+
+impl Param {
+    fn new(name: &str, value: f32, range: RangeInclusive<f32>) -> Self {
+        Param {
+            name: name.to_string(),
+            value,
+            range,
+            gap: 0.0,
+        }
+    }
+
+    fn away_from_zero(self, gap: f32) -> Self {
+        Param { gap, ..self }
+    }
+
+    /// A random value in `range`, but not within `gap` of 0.
+    pub fn random(&self, rng: &mut impl Rng) -> f32 {
+        loop {
+            let value = rng.gen_range(self.range.clone());
+            if value.abs() >= self.gap {
+                return value;
+            }
+        }
+    }
+}
+
+/// Peter de Jong: `x' = sin(a y) - cos(b x)`, `y' = sin(c x) - cos(d y)`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeJong {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+}
+
+impl Attractor for DeJong {
+    fn step(&self, p: Vec2, _r: f32) -> Vec2 {
+        vec2(
+            (self.a * p.y).sin() - (self.b * p.x).cos(),
+            (self.c * p.x).sin() - (self.d * p.y).cos(),
+        )
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::new("a", self.a, -PI..=PI),
+            Param::new("b", self.b, -PI..=PI),
+            Param::new("c", self.c, -PI..=PI),
+            Param::new("d", self.d, -PI..=PI),
+        ]
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        *[&mut self.a, &mut self.b, &mut self.c, &mut self.d][i] = value;
+    }
+}
+
+/// Clifford Pickover: `x' = sin(a y) + c cos(a x)`, `y' = sin(b x) + d cos(b y)`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Clifford {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+}
+
+impl Attractor for Clifford {
+    fn step(&self, p: Vec2, _r: f32) -> Vec2 {
+        vec2(
+            (self.a * p.y).sin() + self.c * (self.a * p.x).cos(),
+            (self.b * p.x).sin() + self.d * (self.b * p.y).cos(),
+        )
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::new("a", self.a, -3.0..=3.0),
+            Param::new("b", self.b, -3.0..=3.0),
+            Param::new("c", self.c, -3.0..=3.0),
+            Param::new("d", self.d, -3.0..=3.0),
+        ]
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        *[&mut self.a, &mut self.b, &mut self.c, &mut self.d][i] = value;
+    }
+}
+
+/// Johnny Svensson: `x' = d sin(a x) - sin(b y)`, `y' = c cos(a x) + cos(b y)`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Svensson {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+}
+
+impl Attractor for Svensson {
+    fn step(&self, p: Vec2, _r: f32) -> Vec2 {
+        vec2(
+            self.d * (self.a * p.x).sin() - (self.b * p.y).sin(),
+            self.c * (self.a * p.x).cos() + (self.b * p.y).cos(),
+        )
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::new("a", self.a, -PI..=PI),
+            Param::new("b", self.b, -PI..=PI),
+            Param::new("c", self.c, -PI..=PI),
+            Param::new("d", self.d, -PI..=PI),
+        ]
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        *[&mut self.a, &mut self.b, &mut self.c, &mut self.d][i] = value;
+    }
+}
+
+/// Ivan Emrich's bedhead: `x' = sin(x y / b) y + cos(a x - y)`, `y' = x + sin(y) / b`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bedhead {
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Attractor for Bedhead {
+    fn step(&self, p: Vec2, _r: f32) -> Vec2 {
+        vec2(
+            (p.x * p.y / self.b).sin() * p.y + (self.a * p.x - p.y).cos(),
+            p.x + p.y.sin() / self.b,
+        )
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::new("a", self.a, -1.0..=1.0),
+            Param::new("b", self.b, -1.0..=1.0).away_from_zero(0.1),
+        ]
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        *[&mut self.a, &mut self.b][i] = value;
+    }
+}
+
+/// Barry Martin's hopalong: `x' = y - sign(x) sqrt(|b x - c|)`, `y' = a - x`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hopalong {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+}
+
+impl Attractor for Hopalong {
+    fn step(&self, p: Vec2, _r: f32) -> Vec2 {
+        vec2(
+            p.y - p.x.signum() * (self.b * p.x - self.c).abs().sqrt(),
+            self.a - p.x,
+        )
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::new("a", self.a, -10.0..=10.0),
+            Param::new("b", self.b, -10.0..=10.0),
+            Param::new("c", self.c, -10.0..=10.0),
+        ]
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        *[&mut self.a, &mut self.b, &mut self.c][i] = value;
+    }
+}
+
+/// Gumowski and Mira's map from particle physics: `x' = y + a (1 - b y²) y + f(x)`,
+/// `y' = f(x') - x`, with `f(x) = mu x + 2 (1 - mu) x² / (1 + x²)`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GumowskiMira {
+    pub a: f32,
+    pub b: f32,
+    pub mu: f32,
+}
+
+impl GumowskiMira {
+    fn f(&self, x: f32) -> f32 {
+        self.mu * x + 2.0 * (1.0 - self.mu) * x * x / (1.0 + x * x)
+    }
+}
+
+impl Attractor for GumowskiMira {
+    fn step(&self, p: Vec2, _r: f32) -> Vec2 {
+        let x = p.y + self.a * (1.0 - self.b * p.y * p.y) * p.y + self.f(p.x);
+        vec2(x, self.f(x) - p.x)
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::new("a", self.a, 0.0..=0.1),
+            Param::new("b", self.b, 0.0..=0.1),
+            Param::new("mu", self.mu, -1.0..=1.0),
+        ]
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        *[&mut self.a, &mut self.b, &mut self.mu][i] = value;
+    }
+}
+
+/// An iterated function system: each step applies one of several affine maps, chosen at
+/// random with the given probabilities.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ifs {
+    pub name: String,
+    #[serde(deserialize_with = "non_empty")]
+    pub maps: Vec<Affine>,
+}
+
+fn non_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Affine>, D::Error> {
+    let maps = Vec::deserialize(deserializer)?;
+    if maps.is_empty() {
+        Err(D::Error::custom("expected at least one map"))
+    } else {
+        Ok(maps)
+    }
+}
+
+/// `x' = a x + b y + e`, `y' = c x + d y + f`, picked with relative probability `p`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Affine {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+    pub p: f32,
+}
+
+impl Affine {
+    fn new([a, b, c, d, e, f, p]: [f32; 7]) -> Self {
+        Affine {
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            p,
+        }
+    }
+}
+
+impl Ifs {
+    /// Barnsley's fern.
+    pub fn fern() -> Self {
+        Ifs {
+            name: "fern".to_string(),
+            maps: vec![
+                Affine::new([0.0, 0.0, 0.0, 0.16, 0.0, 0.0, 0.01]),
+                Affine::new([0.85, 0.04, -0.04, 0.85, 0.0, 1.6, 0.85]),
+                Affine::new([0.2, -0.26, 0.23, 0.22, 0.0, 1.6, 0.07]),
+                Affine::new([-0.15, 0.28, 0.26, 0.24, 0.0, 0.44, 0.07]),
+            ],
+        }
+    }
+
+    /// Sierpinski's triangle.
+    pub fn sierpinski() -> Self {
+        let half = |e, f| Affine::new([0.5, 0.0, 0.0, 0.5, e, f, 1.0]);
+        Ifs {
+            name: "sierpinski".to_string(),
+            maps: vec![half(0.0, 0.0), half(0.5, 0.0), half(0.25, 0.433)],
+        }
+    }
+}
+
+impl Attractor for Ifs {
+    fn step(&self, p: Vec2, r: f32) -> Vec2 {
+        let mut r = r * self.maps.iter().map(|m| m.p).sum::<f32>();
+        let m = self
+            .maps
+            .iter()
+            .find(|m| {
+                r -= m.p;
+                r < 0.0
+            })
+            .unwrap_or(self.maps.last().unwrap());
+        vec2(m.a * p.x + m.b * p.y + m.e, m.c * p.x + m.d * p.y + m.f)
+    }
+
+    fn params(&self) -> Vec<Param> {
+        self.maps
+            .iter()
+            .enumerate()
+            .flat_map(|(i, m)| {
+                let n = i + 1;
+                [
+                    Param::new(&format!("a{}", n), m.a, -1.0..=1.0),
+                    Param::new(&format!("b{}", n), m.b, -1.0..=1.0),
+                    Param::new(&format!("c{}", n), m.c, -1.0..=1.0),
+                    Param::new(&format!("d{}", n), m.d, -1.0..=1.0),
+                    Param::new(&format!("e{}", n), m.e, -2.0..=2.0),
+                    Param::new(&format!("f{}", n), m.f, -2.0..=2.0),
+                    Param::new(&format!("p{}", n), m.p, 0.0..=1.0),
+                ]
+            })
+            .collect()
+    }
+
+    fn set_param(&mut self, i: usize, value: f32) {
+        let m = &mut self.maps[i / 7];
+        *[
+            &mut m.a, &mut m.b, &mut m.c, &mut m.d, &mut m.e, &mut m.f, &mut m.p,
+        ][i % 7] = value;
+    }
+}
+
+/// One of the attractors, as saved in presets.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Map {
+    DeJong(DeJong),
+    Clifford(Clifford),
+    Svensson(Svensson),
+    Bedhead(Bedhead),
+    Hopalong(Hopalong),
+    GumowskiMira(GumowskiMira),
+    Ifs(Ifs),
+}
+
+impl Map {
+    /// Every kind of attractor, with parameters that give a good looking example.
+    pub fn all() -> Vec<Map> {
+        vec![
+            Map::DeJong(DeJong {
+                a: 0.97,
+                b: -1.90,
+                c: 1.38,
+                d: -1.50,
+            }),
+            Map::Clifford(Clifford {
+                a: -1.4,
+                b: 1.6,
+                c: 1.0,
+                d: 0.7,
+            }),
+            Map::Svensson(Svensson {
+                a: 1.5,
+                b: -1.8,
+                c: 1.6,
+                d: 0.9,
+            }),
+            Map::Bedhead(Bedhead { a: -0.81, b: -0.92 }),
+            Map::Hopalong(Hopalong {
+                a: 0.4,
+                b: 1.0,
+                c: 0.0,
+            }),
+            Map::GumowskiMira(GumowskiMira {
+                a: 0.008,
+                b: 0.05,
+                mu: -0.496,
+            }),
+            Map::Ifs(Ifs::fern()),
+            Map::Ifs(Ifs::sierpinski()),
+        ]
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Map::DeJong(_) => "dejong",
+            Map::Clifford(_) => "clifford",
+            Map::Svensson(_) => "svensson",
+            Map::Bedhead(_) => "bedhead",
+            Map::Hopalong(_) => "hopalong",
+            Map::GumowskiMira(_) => "gumowski-mira",
+            Map::Ifs(ifs) => &ifs.name,
+        }
+    }
+
+    /// The example of the next kind of attractor after this one.
+    pub fn next(&self) -> Map {
+        let all = Map::all();
+        let i = all.iter().position(|m| m.name() == self.name());
+        all[i.map_or(0, |i| (i + 1) % all.len())].clone()
+    }
+
+    pub fn attractor(&self) -> &dyn Attractor {
+        match self {
+            Map::DeJong(m) => m,
+            Map::Clifford(m) => m,
+            Map::Svensson(m) => m,
+            Map::Bedhead(m) => m,
+            Map::Hopalong(m) => m,
+            Map::GumowskiMira(m) => m,
+            Map::Ifs(m) => m,
+        }
+    }
+
+    /// The map `t` of the way from this one to `other`, blending each parameter. `None` if the
+    /// two aren't the same kind of map.
+    pub fn lerp(&self, other: &Map, t: f32) -> Option<Map> {
+        let (a, b) = (self.attractor().params(), other.attractor().params());
+        if self.name() != other.name() || a.len() != b.len() {
+            return None;
+        }
+        let mut map = self.clone();
+        for (i, (a, b)) in a.iter().zip(&b).enumerate() {
+            map.attractor_mut()
+                .set_param(i, a.value + (b.value - a.value) * t);
+        }
+        Some(map)
+    }
+
+    pub fn attractor_mut(&mut self) -> &mut dyn Attractor {
+        match self {
+            Map::DeJong(m) => m,
+            Map::Clifford(m) => m,
+            Map::Svensson(m) => m,
+            Map::Bedhead(m) => m,
+            Map::Hopalong(m) => m,
+            Map::GumowskiMira(m) => m,
+            Map::Ifs(m) => m,
+        }
+    }
+}
+
+/// The region most points of the attractor fall in, found by following a few orbits. `r`
+/// supplies the random numbers for `Attractor::step`.
+pub fn fit(attractor: &dyn Attractor, mut r: impl FnMut() -> f32) -> Rect {
+    let mut xs = vec![];
+    let mut ys = vec![];
+    for _ in 0..100 {
+        let mut p = vec2(r() * 4.0 - 2.0, r() * 4.0 - 2.0);
+        for i in 0..500 {
+            p = attractor.step(p, r());
+            if !p.is_finite() {
+                break;
+            }
+            // Skip the way in from the starting point
+            if i >= 20 {
+                xs.push(p.x);
+                ys.push(p.y);
+            }
+        }
+    }
+    if xs.len() < 100 {
+        return Rect::from_w_h(4.0, 4.0);
+    }
+    // Ignore the odd outlier, which would squash everything else
+    let range = |v: &mut Vec<f32>| {
+        v.sort_by(f32::total_cmp);
+        let n = v.len();
+        (v[n / 500], v[n - 1 - n / 500])
+    };
+    let (left, right) = range(&mut xs);
+    let (bottom, top) = range(&mut ys);
+    let rect = Rect::from_corners(vec2(left, bottom), vec2(right, top));
+    if rect.w() > 0.0 && rect.h() > 0.0 {
+        rect
+    } else {
+        Rect::from_xy_wh(rect.xy(), Vec2::splat(1.0))
+    }
+}
@@ -0,0 +1,166 @@
+//! Accumulating orbits into a histogram of how often each pixel was visited, for smooth
+//! renders of millions of points.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use nannou::{image::RgbaImage, prelude::*};
+use nannou_playground::palette::Palette;
+use serde::{Deserialize, Serialize};
+
+/// How visit counts become brightness, as saved in presets.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tone {
+    /// Compress counts logarithmically, so sparse areas stay visible next to dense ones.
+    #[serde(default = "default_log")]
+    pub log: bool,
+    /// Values above 1 brighten faint areas.
+    #[serde(default = "default_gamma")]
+    pub gamma: f32,
+    /// Instead of scaling to the busiest pixel, treat every visit like a dot of this opacity,
+    /// which saturates where visits pile up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f32>,
+}
+
+fn default_log() -> bool {
+    true
+}
+
+fn default_gamma() -> f32 {
+    2.2
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Tone {
+            log: default_log(),
+            gamma: default_gamma(),
+            alpha: None,
+        }
+    }
+}
+
+/// Visit counts for a grid of pixels covering `view`. Points can be added from several threads
+/// at once.
+pub struct Density {
+    width: u32,
+    height: u32,
+    view: Rect,
+    counts: Vec<AtomicU32>,
+}
+
+impl Density {
+    pub fn new(width: u32, height: u32, view: Rect) -> Self {
+        Density {
+            width,
+            height,
+            view,
+            counts: (0..width * height).map(|_| AtomicU32::new(0)).collect(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn add(&self, p: Vec2) {
+        let x = (p.x - self.view.left()) / self.view.w() * self.width as f32;
+        let y = (self.view.top() - p.y) / self.view.h() * self.height as f32;
+        if x >= 0.0 && y >= 0.0 && x < self.width as f32 && y < self.height as f32 {
+            let i = y as usize * self.width as usize + x as usize;
+            // Saturate rather than wrap, so the busiest pixels don't turn dark after long runs.
+            let _ = self.counts[i].fetch_update(Ordering::Relaxed, Ordering::Relaxed, |c| {
+                Some(c.saturating_add(1))
+            });
+        }
+    }
+
+    /// Tone map the counts into an image, fading from `background` for unvisited pixels
+    /// through the colours of `palette` for the most visited ones.
+    pub fn render(&self, tone: &Tone, background: Rgb, palette: &Palette) -> RgbaImage {
+        let counts: Vec<u32> = self
+            .counts
+            .iter()
+            .map(|c| c.load(Ordering::Relaxed))
+            .collect();
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let brightness = |count: u32| {
+            let v = if let Some(alpha) = tone.alpha {
+                1.0 - (1.0 - alpha).powi(count.min(i32::MAX as u32) as i32)
+            } else if tone.log {
+                (count as f32).ln_1p() / max.ln_1p()
+            } else {
+                count as f32 / max
+            };
+            v.powf(1.0 / tone.gamma)
+        };
+        let mut stops = vec![background];
+        stops.extend_from_slice(palette.colors());
+        let gradient = Palette::new("", stops).unwrap();
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (px, &count) in image.pixels_mut().zip(&counts) {
+            let c = gradient.gradient(brightness(count));
+            let byte = |v: f32| (v * 255.0).round() as u8;
+            px.0 = [byte(c.red), byte(c.green), byte(c.blue), 255];
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red() -> Palette {
+        Palette::new("red", vec![rgb(1.0, 0.0, 0.0)]).unwrap()
+    }
+
+    fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+        image.get_pixel(x, y).0
+    }
+
+    #[test]
+    fn points_count_towards_their_pixel() {
+        let density = Density::new(4, 2, Rect::from_w_h(4.0, 2.0));
+        density.add(vec2(-1.5, 0.5));
+        density.add(vec2(-1.9, 0.1));
+        density.add(vec2(1.5, -0.5));
+        // Outside the view
+        density.add(vec2(5.0, 0.0));
+        density.add(vec2(0.0, -1.0));
+        let image = density.render(&Tone::default(), rgb(1.0, 1.0, 1.0), &red());
+        assert_eq!(pixel(&image, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 1, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&image, 2, 1), [255, 255, 255, 255]);
+        let fainter = pixel(&image, 3, 1);
+        assert!(fainter[1] > 0 && fainter[1] < 255, "{:?}", fainter);
+    }
+
+    #[test]
+    fn opaque_visits_saturate_at_once() {
+        let density = Density::new(2, 1, Rect::from_w_h(2.0, 1.0));
+        for _ in 0..10 {
+            density.add(vec2(-0.5, 0.0));
+        }
+        density.add(vec2(0.5, 0.0));
+        let tone = Tone {
+            alpha: Some(1.0),
+            ..Tone::default()
+        };
+        let image = density.render(&tone, rgb(1.0, 1.0, 1.0), &red());
+        assert_eq!(pixel(&image, 0, 0), pixel(&image, 1, 0));
+    }
+
+    #[test]
+    fn counts_saturate_instead_of_wrapping() {
+        let density = Density::new(1, 1, Rect::from_w_h(1.0, 1.0));
+        density.counts[0].store(u32::MAX - 1, Ordering::Relaxed);
+        density.add(Vec2::ZERO);
+        density.add(Vec2::ZERO);
+        assert_eq!(density.counts[0].load(Ordering::Relaxed), u32::MAX);
+    }
+}
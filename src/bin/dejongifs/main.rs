@@ -0,0 +1,411 @@
+use attractor::Map;
+use clap::Parser;
+use density::{Density, Tone};
+use nannou::{
+    prelude::*,
+    rand::{
+        prelude::{SeedableRng, StdRng},
+        Rng,
+    },
+};
+use nannou_egui::egui;
+use nannou_playground::{palette::Palette, Canvas, FrameInfo, Sketch};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tween::Tween;
+
+mod attractor;
+mod density;
+mod search;
+mod tween;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 800;
+const DEFAULT_PALETTE: &str = "ink";
+/// Iterations each point makes after a restart before it counts, to get onto the attractor.
+const WARMUP: u32 = 20;
+/// Points are iterated in chunks of this many, each drawing from its own random generator, so
+/// the result for a seed doesn't depend on how many threads share the work.
+const CHUNK: usize = 1024;
+
+fn main() {
+    // `dejongifs search ...` looks for good parameters instead of opening the sketch
+    if std::env::args().nth(1).as_deref() == Some("search") {
+        let args = search::Args::parse_from(std::env::args().skip(1));
+        search::run(&args).unwrap();
+        return;
+    }
+    nannou_playground::run::<Model>();
+}
+
+struct Model {
+    seed: u64,
+    /// Picks random parameters.
+    rng: StdRng,
+    /// Scatters the points and drives their iteration, starting over from `seed` whenever the
+    /// points restart so the same seed always gives the same picture.
+    point_rng: StdRng,
+    map: Map,
+    points: Vec<Point2>,
+    /// Iterations each point makes per frame.
+    iterations: u32,
+    /// Iterations made since the points restarted.
+    age: u32,
+    /// The part of the plane that is shown, fitted to the attractor.
+    view: Rect,
+    /// Visits so far, allocated at the size of the frame once it is known.
+    density: Option<Density>,
+    tone: Tone,
+    palette: Palette,
+    /// Parameter sets saved from the panel to animate between.
+    saved: [Option<Map>; 2],
+    tween: Option<Tween>,
+}
+
+impl Model {
+    /// Start over from fresh random points, after the map changed.
+    fn restart_points(&mut self) {
+        self.point_rng = StdRng::seed_from_u64(self.seed);
+        self.points = generate_random_points(self.points.len(), &mut self.point_rng);
+        self.age = 0;
+        let fit = |map: &Map| {
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            attractor::fit(map.attractor(), || rng.gen())
+        };
+        // Animations keep the same framing throughout
+        self.view = match &self.tween {
+            Some(tween) => fit(&tween.from).max(fit(&tween.to)),
+            None => fit(&self.map),
+        };
+        self.density = None;
+    }
+
+    fn iterate(&mut self) {
+        let map = self.map.attractor();
+        let density = self.density.as_ref().unwrap();
+        let seeds: Vec<u64> = (0..self.points.len().div_ceil(CHUNK))
+            .map(|_| self.point_rng.gen())
+            .collect();
+        let (iterations, age) = (self.iterations, self.age);
+        self.points
+            .par_chunks_mut(CHUNK)
+            .zip(seeds)
+            .for_each(|(points, seed)| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                for i in 0..iterations {
+                    for p in points.iter_mut() {
+                        *p = map.step(*p, rng.gen());
+                        // Points that escaped to infinity start over somewhere else
+                        if !p.is_finite() {
+                            *p = pt2(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0));
+                        } else if age + i >= WARMUP {
+                            density.add(*p);
+                        }
+                    }
+                }
+            });
+        self.age += iterations;
+    }
+
+    /// A density buffer of `size` pixels, showing the view with a margin and stretched to the
+    /// aspect ratio of `rect`.
+    fn new_density(&self, rect: Rect, (width, height): (u32, u32)) -> Density {
+        let fit = (rect.w() / self.view.w()).min(rect.h() / self.view.h()) * 0.95;
+        let view = Rect::from_xy_wh(self.view.xy(), rect.wh() / fit);
+        Density::new(width, height, view)
+    }
+
+    /// Set parameter `i` of the map to a random value, if it has that many.
+    fn randomize_param(&mut self, i: usize) {
+        if let Some(param) = self.map.attractor().params().get(i) {
+            let value = param.random(&mut self.rng);
+            self.map.attractor_mut().set_param(i, value);
+        }
+    }
+}
+
+fn generate_random_points(n: usize, rng: &mut impl Rng) -> Vec<Point2> {
+    std::iter::from_fn(|| Some(pt2(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0))))
+        .take(n)
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct Params {
+    /// Number of points iterated in parallel.
+    #[serde(default = "default_points")]
+    points: usize,
+    #[serde(default = "default_iterations")]
+    iterations: u32,
+    #[serde(default = "default_palette")]
+    palette: String,
+    #[serde(default)]
+    tone: Tone,
+    map: Map,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tween: Option<Tween>,
+}
+
+fn default_points() -> usize {
+    1000
+}
+
+fn default_iterations() -> u32 {
+    100
+}
+
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
+}
+
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [WIDTH, HEIGHT];
+    type Params = Params;
+
+    fn new() -> Self {
+        let mut model = Model {
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+            point_rng: StdRng::seed_from_u64(0),
+            map: Map::all().remove(0),
+            points: vec![Point2::ZERO; default_points()],
+            iterations: default_iterations(),
+            age: 0,
+            view: Rect::from_w_h(4.0, 4.0),
+            density: None,
+            tone: Tone::default(),
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
+            saved: [None, None],
+            tween: None,
+        };
+        model.restart_points();
+        model
+    }
+
+    fn params(&self) -> Params {
+        Params {
+            points: self.points.len(),
+            iterations: self.iterations,
+            palette: self.palette.name().to_string(),
+            tone: self.tone.clone(),
+            map: self.map.clone(),
+            tween: self.tween.clone(),
+        }
+    }
+
+    fn set_params(&mut self, params: Params) {
+        self.points = vec![Point2::ZERO; params.points];
+        self.iterations = params.iterations;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+        self.tone = params.tone;
+        self.map = params.map;
+        self.tween = params.tween;
+    }
+
+    fn update(&mut self, info: &FrameInfo) {
+        if let Some(tween) = &self.tween {
+            self.map = tween.at(info.frame);
+            // Every frame of an animation is a picture of its own
+            self.density = None;
+        }
+        // A pixel per physical pixel of the frame
+        let size = (info.rect.wh() * info.scale).round().max(Vec2::ONE);
+        let size = (size.x as u32, size.y as u32);
+        if self
+            .density
+            .as_ref()
+            .is_none_or(|d| (d.width(), d.height()) != size)
+        {
+            self.density = Some(self.new_density(info.rect, size));
+        }
+        self.iterate();
+    }
+
+    fn key_pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::A => self.randomize_param(0),
+            Key::B => self.randomize_param(1),
+            Key::C => self.randomize_param(2),
+            Key::D => self.randomize_param(3),
+            Key::M => {
+                self.map = self.map.next();
+                println!("Switched to {}", self.map.name());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.set_seed(seed);
+        for i in 0..self.map.attractor().params().len() {
+            self.randomize_param(i);
+        }
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn summary(&self) -> String {
+        let params = self.map.attractor().params();
+        // IFS have too many parameters to fit in a file name
+        if params.len() > 4 {
+            return self.map.name().to_string();
+        }
+        let values: Vec<_> = params
+            .iter()
+            .map(|p| format!("{}{:.3}", p.name, p.value))
+            .collect();
+        format!("{}-{}", self.map.name(), values.join("-"))
+    }
+
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn reset(&mut self) {
+        self.restart_points();
+    }
+
+    fn ui(&mut self, ctx: &egui::Context) -> bool {
+        let mut restart = false;
+        egui::Window::new("Attractor").show(ctx, |ui| {
+            ui.label(format!("Map: {}", self.map.name()));
+            for (i, param) in self.map.attractor().params().into_iter().enumerate() {
+                let mut value = param.value;
+                let slider = egui::Slider::new(&mut value, param.range).text(param.name);
+                if ui.add(slider).changed() {
+                    self.map.attractor_mut().set_param(i, value);
+                    restart = true;
+                }
+            }
+            let mut points = self.points.len();
+            let slider = egui::Slider::new(&mut points, 100..=10_000_000).logarithmic(true);
+            if ui.add(slider.text("Points")).changed() {
+                self.points.resize(points, Point2::ZERO);
+                restart = true;
+            }
+            ui.add(
+                egui::Slider::new(&mut self.iterations, 1..=1000)
+                    .logarithmic(true)
+                    .text("Iterations per frame"),
+            );
+
+            ui.separator();
+            ui.checkbox(&mut self.tone.log, "Logarithmic");
+            ui.add(egui::Slider::new(&mut self.tone.gamma, 0.5..=5.0).text("Gamma"));
+            let mut blend = self.tone.alpha.is_some();
+            if ui.checkbox(&mut blend, "Blend points").changed() {
+                self.tone.alpha = blend.then_some(0.2);
+            }
+            if let Some(alpha) = &mut self.tone.alpha {
+                let slider = egui::Slider::new(alpha, 0.001..=1.0).logarithmic(true);
+                ui.add(slider.text("Point alpha"));
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save A").clicked() {
+                    self.saved[0] = Some(self.map.clone());
+                }
+                if ui.button("Save B").clicked() {
+                    self.saved[1] = Some(self.map.clone());
+                }
+            });
+            let mut animate = self.tween.is_some();
+            if ui.checkbox(&mut animate, "Animate A to B").changed() {
+                self.tween = match &self.saved {
+                    [Some(a), Some(b)] if animate => Tween::new(a.clone(), b.clone()),
+                    _ => None,
+                };
+                if animate && self.tween.is_none() {
+                    println!("Save two parameter sets of the same map to animate between");
+                }
+                restart = true;
+            }
+            if let Some(tween) = &mut self.tween {
+                let slider = egui::Slider::new(&mut tween.frames, 60..=3600);
+                ui.add(slider.text("Frames per loop"));
+            }
+        });
+        restart
+    }
+
+    fn draw(&self, canvas: &Canvas, info: &FrameInfo) {
+        if let Some(density) = &self.density {
+            let image = density.render(&self.tone, Self::BACKGROUND.into_format(), &self.palette);
+            canvas.image(info.rect, image);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::image::RgbaImage;
+    use nannou_playground::{
+        clock::{Clock, Timing},
+        preset::Preset,
+    };
+
+    use super::*;
+
+    fn seeded(seed: u64) -> Model {
+        let mut model = Model::new();
+        model.reseed(seed);
+        model.reset();
+        model
+    }
+
+    /// Run a few frames from the start, and render the density.
+    fn run(model: &mut Model) -> RgbaImage {
+        let mut clock = Clock::new(Timing::Fixed);
+        for _ in 0..3 {
+            clock.step();
+            model.update(&clock.info(Rect::from_w_h(200.0, 200.0), 1.0));
+        }
+        let density = model.density.as_ref().unwrap();
+        density.render(&model.tone, Model::BACKGROUND.into_format(), &model.palette)
+    }
+
+    #[test]
+    fn restarts_give_the_same_picture() {
+        let mut model = seeded(42);
+        let picture = run(&mut model);
+        model.reset();
+        assert!(run(&mut model) == picture);
+    }
+
+    #[test]
+    fn seeds_give_the_same_picture_on_any_number_of_threads() {
+        let on_threads = |threads: usize, seed: u64| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| run(&mut seeded(seed)))
+        };
+        let picture = on_threads(1, 42);
+        assert!(on_threads(4, 42) == picture);
+        assert!(on_threads(4, 43) != picture);
+    }
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
+}
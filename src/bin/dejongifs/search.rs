@@ -0,0 +1,268 @@
+//! Searching random parameter sets for ones that give interesting attractors, instead of
+//! pressing `R` until something shows up.
+//!
+//! Most random parameters make the orbits settle on a fixed point or a short cycle. Those are
+//! rejected by estimating the Lyapunov exponent (how fast nearby orbits separate, positive for
+//! chaos) and by how much of its bounding box the attractor covers, and the rest are ranked by
+//! their box-counting dimension, which is about 1.5 for the most pleasing ones according to
+//! Sprott.
+
+use std::{error::Error, fmt::Write, fs, path::PathBuf};
+
+use clap::Parser;
+use nannou::{
+    prelude::*,
+    rand::{
+        prelude::{SeedableRng, StdRng},
+        Rng,
+    },
+};
+use nannou_playground::{headless, preset::Preset, raster::Raster, sketch, Sketch};
+
+use crate::{
+    attractor::{Attractor, Map},
+    Model,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "dejongifs search")]
+pub struct Args {
+    /// Number of parameter sets to try.
+    #[arg(long, default_value_t = 1000)]
+    pub tries: u64,
+    /// Number of the best parameter sets to render.
+    #[arg(long, default_value_t = 24)]
+    pub keep: usize,
+    /// Seed of the first parameter set, the others use the seeds after it.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Kind of map to search, e.g. `clifford` (defaults to the map of `--preset`).
+    #[arg(long)]
+    pub map: Option<String>,
+    /// Preset to take the map and rendering settings from.
+    #[arg(long)]
+    pub preset: Option<PathBuf>,
+    /// Directory to write the gallery to.
+    #[arg(long, default_value = "gallery")]
+    pub out: PathBuf,
+    /// Frames to simulate for each thumbnail.
+    #[arg(long, default_value_t = 20)]
+    pub frames: u64,
+    /// Size of the thumbnails relative to the window.
+    #[arg(long, default_value_t = 0.25, value_parser = sketch::positive)]
+    pub scale: f32,
+    /// Box-counting dimension to rank closest to.
+    #[arg(long, default_value_t = 1.5)]
+    pub dimension: f32,
+}
+
+/// Orbit steps made before measuring anything.
+const WARMUP: usize = 1000;
+/// Orbit steps measured.
+const STEPS: usize = 20000;
+/// Distance nearby orbits are kept at to estimate the Lyapunov exponent.
+const SEPARATION: f32 = 1e-4;
+/// Lyapunov exponent below which orbits count as periodic.
+const MIN_LYAPUNOV: f32 = 0.01;
+/// Fraction of the bounding box below which attractors count as too sparse.
+const MIN_COVERAGE: f32 = 0.05;
+
+/// How interesting the attractor of a parameter set looks.
+#[derive(Clone, Copy, Debug)]
+pub struct Score {
+    pub lyapunov: f32,
+    /// Fraction of the cells of a 64×64 grid over the bounding box that are visited.
+    pub coverage: f32,
+    pub dimension: f32,
+}
+
+/// Measure the attractor by following one orbit, or `None` if it escapes to infinity or
+/// collapses to a point.
+pub fn score(attractor: &dyn Attractor, rng: &mut StdRng) -> Option<Score> {
+    let mut p = vec2(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0));
+    for _ in 0..WARMUP {
+        p = attractor.step(p, rng.gen());
+    }
+    let mut q = p + vec2(SEPARATION, 0.0);
+    let mut stretch = 0.0;
+    let mut points = Vec::with_capacity(STEPS);
+    for _ in 0..STEPS {
+        // Both orbits must make the same random choices, for maps that make any
+        let r = rng.gen();
+        p = attractor.step(p, r);
+        q = attractor.step(q, r);
+        if !p.is_finite() || !q.is_finite() {
+            return None;
+        }
+        let d = p.distance(q);
+        if d == 0.0 {
+            // The orbits merged, as they do on a fixed point
+            return None;
+        }
+        stretch += (d / SEPARATION).ln();
+        q = p + (q - p) * SEPARATION / d;
+        points.push(p);
+    }
+
+    let (min, max) = points
+        .iter()
+        .fold((points[0], points[0]), |(min, max), &p| {
+            (min.min(p), max.max(p))
+        });
+    let size = (max - min).max_element();
+    if size < 1e-3 {
+        return None;
+    }
+    // Count the boxes of grids of several sizes that the orbit visits
+    let boxes = |n: u32| {
+        let mut visited = vec![false; (n * n) as usize];
+        for p in &points {
+            let cell = ((*p - min) / size * n as f32).min(Vec2::splat(n as f32 - 1.0));
+            visited[cell.y as usize * n as usize + cell.x as usize] = true;
+        }
+        visited.iter().filter(|&&v| v).count() as f32
+    };
+    let area = (max - min) / size;
+    let coverage = boxes(64) / (64.0 * 64.0 * area.x.max(1.0 / 64.0) * area.y.max(1.0 / 64.0));
+    // The dimension is the slope of log(boxes) against log(grid size)
+    let samples: Vec<Vec2> = [4, 8, 16, 32, 64]
+        .iter()
+        .map(|&n| vec2((n as f32).ln(), boxes(n).ln()))
+        .collect();
+    let mean = samples.iter().sum::<Vec2>() / samples.len() as f32;
+    let (cov, var) = samples.iter().fold((0.0, 0.0), |(cov, var), s| {
+        let d = *s - mean;
+        (cov + d.x * d.y, var + d.x * d.x)
+    });
+    Some(Score {
+        lyapunov: stretch / STEPS as f32,
+        coverage: coverage.min(1.0),
+        dimension: cov / var,
+    })
+}
+
+/// Whether the score looks like a strange attractor. IFS contract by design, their chaos is
+/// all in the random choice of map, so they only need to cover enough.
+fn accept(map: &Map, score: &Score) -> bool {
+    let chaotic = matches!(map, Map::Ifs(_)) || score.lyapunov > MIN_LYAPUNOV;
+    chaotic && score.coverage > MIN_COVERAGE && score.dimension > 1.0
+}
+
+/// Try `args.tries` seeds, then render the best ones with their presets into `args.out`,
+/// along with an `index.html` listing them.
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut base = Model::new();
+    if let Some(path) = &args.preset {
+        Preset::load(path)?.apply(&mut base);
+    }
+    base.tween = None;
+    if let Some(name) = &args.map {
+        base.map = Map::all()
+            .into_iter()
+            .find(|m| m.name() == name)
+            .ok_or_else(|| format!("no map named {:?}", name))?;
+    }
+
+    let mut found = vec![];
+    let mut model = Model::new();
+    model.set_params(base.params());
+    for seed in args.seed..args.seed + args.tries {
+        model.reseed(seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        if let Some(score) = score(model.map.attractor(), &mut rng) {
+            if accept(&model.map, &score) {
+                found.push((seed, score));
+            }
+        }
+    }
+    println!(
+        "{} of {} parameter sets look chaotic",
+        found.len(),
+        args.tries
+    );
+    found.sort_by(|(_, a), (_, b)| {
+        let off = |s: &Score| (s.dimension - args.dimension).abs();
+        off(a).total_cmp(&off(b))
+    });
+    found.truncate(args.keep);
+
+    fs::create_dir_all(&args.out)?;
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<title>dejongifs search</title>\n\
+         <style>body { font-family: sans-serif } figure { display: inline-block }</style>\n",
+    );
+    for (rank, (seed, score)) in found.iter().enumerate() {
+        let mut model = Model::new();
+        model.set_params(base.params());
+        model.reseed(*seed);
+        model.reset();
+        let [width, height] = Model::SIZE;
+        let mut raster = Raster::scaled(width, height, args.scale)?;
+        let info = headless::simulate(&mut model, args.frames, &mut raster, None)?;
+        let name = format!("{:02}-{}.png", rank + 1, model.file_stem());
+        let path = args.out.join(&name);
+        raster.save_png(&path)?;
+        sketch::save_sidecar(&model, &path, &info, args.scale)?;
+
+        let caption = format!(
+            "seed {}, λ {:.3}, coverage {:.0}%, dimension {:.2}",
+            seed,
+            score.lyapunov,
+            score.coverage * 100.0,
+            score.dimension
+        );
+        println!("{:2}. {}: {}", rank + 1, model.summary(), caption);
+        writeln!(
+            html,
+            "<figure><img src=\"{}\"><figcaption>{}.<br>{}<br>{}</figcaption></figure>",
+            name,
+            rank + 1,
+            model.summary(),
+            caption
+        )?;
+    }
+    fs::write(args.out.join("index.html"), html)?;
+    println!("Saved {}", args.out.join("index.html").display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractor::{DeJong, Ifs};
+
+    fn score_of(map: &Map, seed: u64) -> Option<Score> {
+        score(map.attractor(), &mut StdRng::seed_from_u64(seed))
+    }
+
+    #[test]
+    fn scores_only_depend_on_the_seed() {
+        let map = Map::all().remove(0);
+        let a = score_of(&map, 7).unwrap();
+        let b = score_of(&map, 7).unwrap();
+        assert_eq!(
+            (a.lyapunov, a.coverage, a.dimension),
+            (b.lyapunov, b.coverage, b.dimension)
+        );
+        assert!(accept(&map, &a), "{:?}", a);
+    }
+
+    #[test]
+    fn fixed_points_are_rejected() {
+        let map = Map::DeJong(DeJong {
+            a: 0.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+        });
+        assert!(score_of(&map, 7).is_none());
+    }
+
+    #[test]
+    fn sierpinski_has_its_dimension() {
+        let map = Map::Ifs(Ifs::sierpinski());
+        let score = score_of(&map, 7).unwrap();
+        assert!((score.dimension - 3.0f32.log2()).abs() < 0.15, "{:?}", score);
+        assert!(accept(&map, &score));
+    }
+}
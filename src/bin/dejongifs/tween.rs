@@ -0,0 +1,42 @@
+//! Animating smoothly back and forth between two parameter sets, for videos.
+
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use crate::attractor::Map;
+
+/// Two parameter sets of the same kind of map, as saved in presets.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tween {
+    /// Frames to go from `from` to `to` and back, so recordings of this length loop.
+    #[serde(default = "default_frames")]
+    pub frames: u32,
+    pub from: Map,
+    pub to: Map,
+}
+
+fn default_frames() -> u32 {
+    600
+}
+
+impl Tween {
+    /// `None` unless both maps are the same kind.
+    pub fn new(from: Map, to: Map) -> Option<Self> {
+        from.lerp(&to, 0.0)?;
+        Some(Tween {
+            frames: default_frames(),
+            from,
+            to,
+        })
+    }
+
+    /// The map at `frame`, easing in and out at both ends.
+    pub fn at(&self, frame: u64) -> Map {
+        let phase = (frame % self.frames.max(1) as u64) as f32 / self.frames.max(1) as f32;
+        let t = (1.0 - (phase * TAU).cos()) / 2.0;
+        self.from
+            .lerp(&self.to, t)
+            .unwrap_or_else(|| self.from.clone())
+    }
+}
@@ -1,122 +1,518 @@
 use nannou::{
-    noise::{NoiseFn, Perlin},
     prelude::*,
+    rand::{
+        prelude::{SeedableRng, StdRng},
+        Rng,
+    },
 };
-use ndarray::Array2;
+use nannou_egui::egui;
+use nannou_playground::{
+    canvas::Stroke,
+    field::{Field, Noise, NoiseKind, Source},
+    flow::{FlowField, Integrator, Interpolation},
+    overlay::FieldOverlay,
+    palette::Palette,
+    streamlines::{Occupancy, Spacing},
+    Canvas, FrameInfo, Mode, Sketch,
+};
+use serde::{Deserialize, Serialize};
 
 const WIDTH: usize = 500;
 const HEIGHT: usize = 500;
+const DEFAULT_PALETTE: &str = "ink";
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou_playground::run::<Model>();
 }
 
 struct Model {
-    field: Array2<f32>,
+    seed: u64,
+    /// Picks the starting points of the lines.
+    rng: StdRng,
+    field: FlowField,
+    /// What `field` is sampled from.
+    source: Field,
+    /// Nodes of the field along each side.
+    resolution: usize,
+    interpolation: Interpolation,
+    integrator: Integrator,
     num_steps: usize,
     step_length: f32,
-    frame_count: u64,
-}
-
-fn model(app: &App) -> Model {
-    let perlin = Perlin::new();
-    let _window = app
-        .new_window()
-        .size(800, 800)
-        .title(app.exe_name().unwrap())
-        .view(view)
-        .key_pressed(key_pressed)
-        .resized(window_resized)
-        .build();
-
-    let mut field = ndarray::Array2::zeros((WIDTH, HEIGHT));
-    field.indexed_iter_mut().for_each(|((x, y), a)| {
-        let px = x as f64 * 0.005;
-        let py = y as f64 * 0.005;
-        // Generate a random angle from 0 to 2*PI
-        *a = TAU * perlin.get([px, py]) as f32;
-    });
-    Model {
-        field,
-        num_steps: 50,
-        step_length: 1.0,
-        frame_count: 0,
-    }
-}
-
-fn key_pressed(app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::Left => {
-            if model.step_length > 0.1 {
-                model.step_length -= 0.1
+    placement: Placement,
+    /// Distance between evenly placed lines.
+    separation: f32,
+    style: Style,
+    palette: Palette,
+    /// Picks the widths and colours of the lines, apart from `rng` so that restyling a drawing
+    /// keeps its lines in place.
+    style_rng: StdRng,
+    /// Every line drawn since the last reset, so the drawing can be exported as a whole.
+    lines: Vec<Line>,
+    /// The points of `lines`, for stopping new lines where they run into them.
+    occupancy: Occupancy,
+    /// How many of `lines` were added in the last update and still need drawing.
+    new_lines: usize,
+    overlay: FieldOverlay,
+}
+
+/// Where lines start.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Placement {
+    /// A new line from a random point every frame, overlapping earlier ones.
+    #[default]
+    Random,
+    /// All at once, evenly spaced and running until they come close to another line, for a
+    /// finished drawing.
+    Even,
+}
+
+/// How lines are drawn, after Tyler Hobbs' essay on flow fields.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Style {
+    /// Each line gets a width picked at random between these.
+    #[serde(default = "default_widths")]
+    widths: [f32; 2],
+    #[serde(default = "default_alpha")]
+    alpha: f32,
+    /// Fraction of a line at either end over which it narrows to a point.
+    #[serde(default)]
+    taper: f32,
+    /// Filled bands with darker edges instead of strokes.
+    #[serde(default)]
+    ribbons: bool,
+    /// Stop lines before they come within `margin` of an earlier line, instead of crossing it.
+    #[serde(default)]
+    collide: bool,
+    #[serde(default = "default_margin")]
+    margin: f32,
+    /// Lines cut shorter than this by collisions are left out.
+    #[serde(default)]
+    min_length: f32,
+}
+
+fn default_widths() -> [f32; 2] {
+    [0.5, 0.5]
+}
+
+fn default_alpha() -> f32 {
+    0.4
+}
+
+fn default_margin() -> f32 {
+    1.0
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            widths: default_widths(),
+            alpha: default_alpha(),
+            taper: 0.0,
+            ribbons: false,
+            collide: false,
+            margin: default_margin(),
+            min_length: 0.0,
+        }
+    }
+}
+
+impl Style {
+    /// The width of the widest lines.
+    fn max_width(&self) -> f32 {
+        self.widths[0].max(self.widths[1])
+    }
+}
+
+/// A traced line and how it is drawn.
+struct Line {
+    points: Vec<Vec2>,
+    width: f32,
+    color: Rgb,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Params {
+    num_steps: usize,
+    step_length: f32,
+    #[serde(default = "default_resolution")]
+    resolution: usize,
+    #[serde(default)]
+    interpolation: Interpolation,
+    #[serde(default)]
+    integrator: Integrator,
+    #[serde(default)]
+    placement: Placement,
+    #[serde(default = "default_separation")]
+    separation: f32,
+    #[serde(default = "default_palette")]
+    palette: String,
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_field")]
+    field: Field,
+}
+
+fn default_resolution() -> usize {
+    100
+}
+
+fn default_separation() -> f32 {
+    6.0
+}
+
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
+}
+
+/// Directions at angles from a Perlin noise.
+fn default_field() -> Field {
+    Field::new(Source::Noise {
+        noise: Noise::new(NoiseKind::Perlin, 0.005),
+        turns: 1.0,
+    })
+}
+
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [800, 800];
+    const MODE: Mode = Mode::Accumulate;
+
+    type Params = Params;
+
+    fn new() -> Self {
+        let mut model = Model {
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+            field: FlowField::from_fn(Rect::from_w_h(1.0, 1.0), 2, 2, |_| Vec2::ZERO),
+            source: default_field(),
+            resolution: default_resolution(),
+            interpolation: Interpolation::default(),
+            integrator: Integrator::default(),
+            num_steps: 50,
+            step_length: 1.0,
+            placement: Placement::default(),
+            separation: default_separation(),
+            style: Style::default(),
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
+            style_rng: StdRng::seed_from_u64(0),
+            lines: vec![],
+            occupancy: Occupancy::new(Rect::from_w_h(1.0, 1.0), 1.0),
+            new_lines: 0,
+            overlay: FieldOverlay::default(),
+        };
+        model.generate_field();
+        model.reset();
+        model
+    }
+
+    fn params(&self) -> Params {
+        Params {
+            num_steps: self.num_steps,
+            step_length: self.step_length,
+            resolution: self.resolution,
+            interpolation: self.interpolation,
+            integrator: self.integrator,
+            placement: self.placement,
+            separation: self.separation,
+            palette: self.palette.name().to_string(),
+            style: self.style.clone(),
+            field: self.source.clone(),
+        }
+    }
+
+    fn set_params(&mut self, params: Params) {
+        self.num_steps = params.num_steps;
+        self.step_length = params.step_length;
+        // Interpolating needs a node at either end of each side
+        if params.resolution >= 2 {
+            self.resolution = params.resolution;
+        } else {
+            println!(
+                "Keeping resolution {}: {} is too coarse for a field",
+                self.resolution, params.resolution
+            );
+        }
+        self.interpolation = params.interpolation;
+        self.integrator = params.integrator;
+        self.placement = params.placement;
+        self.separation = params.separation;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+        self.style = params.style;
+        self.source = params.field;
+        self.generate_field();
+    }
+
+    fn update(&mut self, _info: &FrameInfo) {
+        match self.placement {
+            Placement::Random => {
+                let line = self.trace_line();
+                self.add_line(line);
+                self.new_lines = 1;
             }
+            Placement::Even if self.lines.is_empty() => {
+                for line in self.even_lines() {
+                    self.add_line(line);
+                }
+                self.new_lines = self.lines.len();
+                println!("Placed {} lines", self.lines.len());
+            }
+            Placement::Even => self.new_lines = 0,
         }
-        Key::Right => model.step_length += 0.1,
-        Key::Down => {
-            if model.num_steps > 1 {
-                model.num_steps -= 1
+    }
+
+    fn key_pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::E => {
+                self.placement = match self.placement {
+                    Placement::Random => Placement::Even,
+                    Placement::Even => Placement::Random,
+                };
+                return true;
             }
+            Key::O => self.overlay.cycle(),
+            Key::Left if self.step_length > 0.1 => self.step_length -= 0.1,
+            Key::Right => self.step_length += 0.1,
+            Key::Down if self.num_steps > 1 => self.num_steps -= 1,
+            Key::Up => self.num_steps += 1,
+            _ => {}
         }
-        Key::Up => model.num_steps += 1,
-        Key::S => app.main_window().capture_frame("flowfield.png"),
-        _ => {}
+        false
     }
-}
 
-fn window_resized(_app: &App, model: &mut Model, _: Vec2) {
-    model.frame_count = 0;
-}
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.generate_field();
+    }
+
+    fn summary(&self) -> String {
+        match self.placement {
+            Placement::Random => format!("steps{}-len{:.1}", self.num_steps, self.step_length),
+            Placement::Even => format!("even{:.1}-len{:.1}", self.separation, self.step_length),
+        }
+    }
+
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn reset(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.style_rng = StdRng::seed_from_u64(self.seed.wrapping_add(1));
+        self.lines.clear();
+        let cell_size = self.style.max_width() + self.style.margin;
+        self.occupancy = Occupancy::new(self.field.rect(), cell_size.max(1.0));
+        self.new_lines = 0;
+    }
+
+    fn ui(&mut self, ctx: &egui::Context) -> bool {
+        let before = self.style.clone();
+        egui::Window::new("Style").show(ctx, |ui| {
+            let style = &mut self.style;
+            let [min, max] = &mut style.widths;
+            ui.add(
+                egui::Slider::new(min, 0.1..=20.0)
+                    .logarithmic(true)
+                    .text("Min width"),
+            );
+            ui.add(
+                egui::Slider::new(max, 0.1..=20.0)
+                    .logarithmic(true)
+                    .text("Max width"),
+            );
+            ui.add(egui::Slider::new(&mut style.alpha, 0.01..=1.0).text("Opacity"));
+            ui.add(egui::Slider::new(&mut style.taper, 0.0..=0.5).text("Taper"));
+            ui.checkbox(&mut style.ribbons, "Ribbons");
+            ui.checkbox(&mut style.collide, "Stop at other lines");
+            if style.collide {
+                ui.add(egui::Slider::new(&mut style.margin, 0.0..=10.0).text("Margin"));
+                let slider = egui::Slider::new(&mut style.min_length, 0.0..=100.0);
+                ui.add(slider.text("Min length"));
+            }
+        });
+        egui::Window::new("Field").show(ctx, |ui| self.overlay.ui(ui));
+        self.style != before
+    }
+
+    fn draw(&self, canvas: &Canvas, info: &FrameInfo) {
+        let draw = field_canvas(canvas, info);
+        for line in &self.lines[self.lines.len() - self.new_lines..] {
+            draw_line(&draw, line, &self.style);
+        }
+    }
 
-fn update(_app: &App, model: &mut Model, _update: Update) {
-    model.frame_count += 1;
+    fn draw_overlay(&self, canvas: &Canvas, info: &FrameInfo) {
+        let draw = field_canvas(canvas, info);
+        self.overlay
+            .draw(&draw, self.field.rect(), |p| self.field.sample(p));
+    }
+
+    /// Draw every line since the last reset at once.
+    fn draw_vector(&self, canvas: &Canvas, info: &FrameInfo) {
+        let draw = field_canvas(canvas, info);
+        for line in &self.lines {
+            draw_line(&draw, line, &self.style);
+        }
+    }
 }
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let window = app.window_rect();
-    let boundary = window.pad(20.0);
+impl Model {
+    /// Sample the field on a grid of `resolution` × `resolution` nodes over the drawing.
+    fn generate_field(&mut self) {
+        let rect = Rect::from_corners(Vec2::ZERO, vec2(WIDTH as f32, HEIGHT as f32));
+        let sampler = match self.source.sampler(self.seed, rect, true) {
+            Ok(sampler) => sampler,
+            Err(e) => {
+                println!("Couldn't build the field, using the default one: {}", e);
+                self.source = default_field();
+                self.source.sampler(self.seed, rect, true).unwrap()
+            }
+        };
+        self.field = FlowField::from_fn(rect, self.resolution, self.resolution, |p| {
+            sampler.at(p, 0.0)
+        })
+        .with_interpolation(self.interpolation);
+    }
+
+    /// Give a line its width and colour and, if lines collide, cut it short where it would come
+    /// too close to an earlier one.
+    fn add_line(&mut self, mut points: Vec<Vec2>) {
+        let [a, b] = self.style.widths;
+        let width = if a == b {
+            a
+        } else {
+            self.style_rng.gen_range(a.min(b)..a.max(b))
+        };
+        let color = self.palette.choose(&mut self.style_rng);
+        if self.style.collide {
+            // Wide enough apart for the widest neighbour
+            let d = (width + self.style.max_width()) / 2.0 + self.style.margin;
+            let end = points
+                .iter()
+                .position(|&p| !self.occupancy.is_free(p, d, None))
+                .unwrap_or(points.len());
+            points.truncate(end);
+            if (end as f32) * self.step_length < self.style.min_length {
+                points.clear();
+            }
+            for (i, &p) in points.iter().enumerate() {
+                self.occupancy
+                    .insert(p, self.lines.len(), i as f32 * self.step_length);
+            }
+        }
+        self.lines.push(Line {
+            points,
+            width,
+            color,
+        });
+    }
 
-    let wdraw = app.draw();
-    // wdraw.rect().wh(window.wh()).rgba(0.0, 0.0, 0.0, 0.001);
+    /// Follow the field from a random starting point.
+    fn trace_line(&mut self) -> Vec<Vec2> {
+        /* let mut x: f32 = random_range(100.0, 400.0);
+        let mut y: f32 = random_range(100.0, 400.0); */
+        let x = self.rng.gen_range(0.0..WIDTH as f32);
+        let y = self.rng.gen_range(0.0..HEIGHT as f32);
+        self.field.trace(
+            vec2(x, y),
+            self.num_steps,
+            self.step_length,
+            self.integrator,
+        )
+    }
 
-    // Puts (0,0) in the top-left corner and y pointing down
-    let draw = wdraw
-        .x_y(-boundary.w() / 2.0, boundary.h() / 2.0)
-        .scale_x(boundary.w() / WIDTH as f32)
-        .scale_y(-boundary.h() / WIDTH as f32);
-    if model.frame_count == 1 {
-        wdraw.background().color(FLORALWHITE);
-        draw.background().color(FLORALWHITE);
+    /// Fill the field with evenly spaced lines, starting from a random point.
+    fn even_lines(&mut self) -> Vec<Vec<Vec2>> {
+        let x = self.rng.gen_range(0.0..WIDTH as f32);
+        let y = self.rng.gen_range(0.0..HEIGHT as f32);
+        let spacing = Spacing {
+            separation: self.separation,
+            test: 0.5,
+            step: self.step_length,
+            integrator: self.integrator,
+        };
+        spacing.place(&self.field, vec2(x, y)).unwrap_or_else(|e| {
+            println!("Couldn't place lines: {}", e);
+            vec![]
+        })
     }
+}
 
-    /* model.field.indexed_iter().for_each(|((x, y), a)| {
-        draw.arrow()
-            .weight(0.2)
-            .points(pt2(x as f32, y as f32), pt2(x as f32 + a.sin() * 0.8, y as f32 + a.cos() * 0.8))
-            .hsv(a / TAU, 0.8, 0.8);
-    }); */
-    /* let mut x: f32 = random_range(100.0, 400.0);
-    let mut y: f32 = random_range(100.0, 400.0); */
-    let mut x = random_range(0.0, WIDTH as f32);
-    let mut y = random_range(0.0, HEIGHT as f32);
+/// Puts (0,0) in the top-left corner and y pointing down, with the field covering the window
+/// minus a margin.
+fn field_canvas(canvas: &Canvas, info: &FrameInfo) -> Canvas {
+    canvas.top_left(info.rect.pad(20.0), vec2(WIDTH as f32, HEIGHT as f32))
+}
 
-    let mut pts = Vec::with_capacity(model.num_steps);
-    for _ in 0..model.num_steps {
-        let px = x.floor().clamp(0.0, (WIDTH - 1) as f32) as usize;
-        let py = y.floor().clamp(0.0, (HEIGHT - 1) as f32) as usize;
-        let angle = model.field[(px, py)];
-        pts.push(vec2(x, y));
-        // draw.ellipse().x_y(x, y).w_h(0.5, 0.5).hsv(angle / TAU, 0.8, 0.8);
-        x += angle.sin() * model.step_length;
-        y += angle.cos() * model.step_length;
+fn draw_line(draw: &Canvas, line: &Line, style: &Style) {
+    let pts = &line.points;
+    if pts.len() < 2 {
+        return;
     }
+    let c = line.color;
+    let color = srgba(c.red, c.green, c.blue, style.alpha);
+    if style.taper <= 0.0 && !style.ribbons {
+        draw.polyline(
+            pts.iter().copied(),
+            Stroke::new(line.width, color).join_round().caps_round(),
+        );
+        return;
+    }
+    let outline = ribbon(pts, line.width, style.taper);
+    draw.polygon(outline.iter().copied(), color);
+    if style.ribbons {
+        let edge = Stroke::new(0.3, srgb(c.red * 0.5, c.green * 0.5, c.blue * 0.5)).join_round();
+        draw.polyline(outline[..pts.len()].iter().copied(), edge);
+        draw.polyline(outline[pts.len()..].iter().copied(), edge);
+    }
+}
 
-    draw.path()
-        .stroke()
-        .stroke_weight(0.5)
-        .hsva(0.0, 0.0, 0.05, 0.4)
-        .join_round()
-        .caps_round()
-        .points(pts);
+/// The outline of a band `width` wide along `pts`, narrowing to a point over `taper` of its
+/// length at either end: one side forwards, then the other backwards.
+fn ribbon(pts: &[Vec2], width: f32, taper: f32) -> Vec<Vec2> {
+    let mut along = vec![0.0; pts.len()];
+    for i in 1..pts.len() {
+        along[i] = along[i - 1] + pts[i].distance(pts[i - 1]);
+    }
+    let length = along[pts.len() - 1].max(f32::EPSILON);
+    let side = |i: usize, sign: f32| {
+        let next = pts[(i + 1).min(pts.len() - 1)];
+        let prev = pts[i.saturating_sub(1)];
+        let normal = (next - prev).perp().normalize_or_zero();
+        let t = along[i] / length;
+        let scale = if taper > 0.0 {
+            (t.min(1.0 - t) / taper).min(1.0)
+        } else {
+            1.0
+        };
+        pts[i] + normal * sign * width / 2.0 * scale
+    };
+    (0..pts.len())
+        .map(|i| side(i, 1.0))
+        .chain((0..pts.len()).rev().map(|i| side(i, -1.0)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou_playground::preset::Preset;
 
-    draw.to_frame(app, &frame).unwrap();
+    use super::*;
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
 }
@@ -1,35 +1,46 @@
+use std::error::Error;
+
 use nannou::{
-    noise::{NoiseFn, Perlin},
     prelude::*,
+    rand::{
+        prelude::{SeedableRng, StdRng},
+        Rng,
+    },
+};
+use nannou_egui::egui;
+use nannou_playground::{
+    canvas::Stroke,
+    field::{Field, Noise, NoiseKind, Sampler, Source},
+    overlay::{FieldOverlay, FieldView},
+    palette::Palette,
+    Canvas, FrameInfo, Mode, Sketch,
 };
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 const WIDTH: usize = 500;
 const HEIGHT: usize = 500;
+const DEFAULT_PALETTE: &str = "ink";
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou_playground::run::<Model>();
 }
 
 struct Flowfield {
-    perlin: Perlin,
+    sampler: Sampler,
     z_off: f64,
 }
 impl Flowfield {
-    pub fn new() -> Self {
-        Self {
-            perlin: Perlin::new(),
+    pub fn new(field: &Field, seed: u64) -> Result<Self, Box<dyn Error>> {
+        let rect = Rect::from_corners(Vec2::ZERO, vec2(WIDTH as f32, HEIGHT as f32));
+        Ok(Self {
+            sampler: field.sampler(seed, rect, true)?,
             z_off: 0.0,
-        }
+        })
     }
 
     pub fn at(&self, p: Vec2) -> Vec2 {
-        let px = p.x as f64 * 0.005;
-        let py = p.y as f64 * 0.005;
-        // Generate a random angle from 0 to 2*PI
-        let angle = TAU
-            * (0.7 * self.perlin.get([px, py, self.z_off])
-                + 0.3 * self.perlin.get([px * 5.0, py * 5.0, self.z_off])) as f32;
-        vec2(angle.sin(), angle.cos())
+        self.sampler.at(p, self.z_off)
     }
 
     pub fn update(&mut self) {
@@ -86,15 +97,44 @@ impl Particle {
 }
 
 struct Model {
+    seed: u64,
     field: Flowfield,
+    /// What `field` is sampled from.
+    source: Field,
     accel_factor: f32,
-    frame_count: u64,
+    num_particles: usize,
     particles: Vec<Particle>,
+    /// Particles take its colours in turn.
+    palette: Palette,
+    overlay: FieldOverlay,
 }
 
 impl Model {
+    /// Start over with a fresh field and particles generated from `self.seed`.
+    fn generate(&mut self) {
+        self.field = match Flowfield::new(&self.source, self.seed) {
+            Ok(field) => field,
+            Err(e) => {
+                println!("Couldn't build the field, using the default one: {}", e);
+                self.source = default_field();
+                Flowfield::new(&self.source, self.seed).unwrap()
+            }
+        };
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        self.particles.clear();
+        for _ in 0..self.num_particles {
+            let p = pt2(
+                rng.gen_range(0.0..WIDTH as f32),
+                rng.gen_range(0.0..HEIGHT as f32),
+            );
+            self.particles.push(Particle::new(p));
+        }
+    }
+
+    /// Particles only depend on the field, so they can move in parallel and still give the
+    /// same result every time.
     fn update_particles(&mut self) {
-        self.particles.iter_mut().for_each(|part| {
+        self.particles.par_iter_mut().for_each(|part| {
             part.update_prev();
             part.apply_force(self.field.at(part.cur_pos) * self.accel_factor);
             part.wrap();
@@ -102,86 +142,167 @@ impl Model {
     }
 }
 
-fn model(app: &App) -> Model {
-    let _window = app
-        .new_window()
-        .size(800, 800)
-        .title(app.exe_name().unwrap())
-        .view(view)
-        .key_pressed(key_pressed)
-        .resized(window_resized)
-        .build();
-
-    // Create particles
-    let num_part = 2000;
-    let mut particles = Vec::with_capacity(num_part);
-    for _ in 0..num_part {
-        let p = pt2(
-            random_range(0.0, WIDTH as f32),
-            random_range(0.0, HEIGHT as f32),
-        );
-        particles.push(Particle::new(p));
-    }
-
-    Model {
-        field: Flowfield::new(),
-        accel_factor: 1.0,
-        frame_count: 0,
-        particles,
-    }
+#[derive(Serialize, Deserialize)]
+struct Params {
+    accel_factor: f32,
+    #[serde(default = "default_particles")]
+    particles: usize,
+    #[serde(default = "default_palette")]
+    palette: String,
+    #[serde(default = "default_field")]
+    field: Field,
 }
 
-fn key_pressed(app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::Left => {
-            if model.accel_factor > 0.1 {
-                model.accel_factor -= 0.1
-            }
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
+}
+
+fn default_particles() -> usize {
+    2000
+}
+
+/// Angles from two octaves of Perlin noise, the finer one five times smaller and weaker.
+fn default_field() -> Field {
+    Field::new(Source::Noise {
+        noise: Noise {
+            octaves: 2,
+            lacunarity: 5.0,
+            gain: 0.3 / 0.7,
+            ..Noise::new(NoiseKind::Perlin, 0.005)
+        },
+        turns: 1.0,
+    })
+}
+
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [800, 800];
+    const MODE: Mode = Mode::Accumulate;
+
+    type Params = Params;
+
+    fn new() -> Self {
+        let mut model = Model {
+            seed: 0,
+            field: Flowfield::new(&default_field(), 0).unwrap(),
+            source: default_field(),
+            accel_factor: 1.0,
+            num_particles: default_particles(),
+            particles: vec![],
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
+            overlay: FieldOverlay::default(),
+        };
+        model.generate();
+        model
+    }
+
+    fn params(&self) -> Params {
+        Params {
+            accel_factor: self.accel_factor,
+            particles: self.num_particles,
+            field: self.source.clone(),
+            palette: self.palette.name().to_string(),
         }
-        Key::Right => model.accel_factor += 0.1,
-        Key::R => model.frame_count = 0,
-        Key::S => app
-            .main_window()
-            .capture_frame(format!("{}.png", app.exe_name().unwrap())),
-        _ => {}
     }
-}
 
-fn window_resized(_app: &App, model: &mut Model, _: Vec2) {
-    model.frame_count = 0;
+    fn set_params(&mut self, params: Params) {
+        self.accel_factor = params.accel_factor;
+        self.num_particles = params.particles;
+        self.source = params.field;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+    }
+
+    fn update(&mut self, _info: &FrameInfo) {
+        self.update_particles();
+        self.field.update();
+    }
+
+    fn key_pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::O => self.overlay.cycle(),
+            Key::Left if self.accel_factor > 0.1 => self.accel_factor -= 0.1,
+            Key::Right => self.accel_factor += 0.1,
+            _ => {}
+        }
+        false
+    }
+
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn summary(&self) -> String {
+        format!("accel{:.1}", self.accel_factor)
+    }
+
+    fn reset(&mut self) {
+        self.generate();
+    }
+
+    fn ui(&mut self, ctx: &egui::Context) -> bool {
+        egui::Window::new("Field").show(ctx, |ui| {
+            self.overlay.ui(ui);
+            ui.label(format!("Noise slice z = {:.2}", self.field.z_off));
+        });
+        false
+    }
+
+    fn draw(&self, canvas: &Canvas, info: &FrameInfo) {
+        let draw = field_canvas(canvas, info);
+
+        let colors = self.palette.colors();
+        for (i, part) in self.particles.iter().enumerate() {
+            let c = colors[i % colors.len()];
+            draw.polyline(
+                [part.prev_pos, part.cur_pos],
+                Stroke::new(0.5, srgba(c.red, c.green, c.blue, 0.05)).join_round(),
+            );
+        }
+    }
+
+    /// The field as it is now, i.e. the slice of the noise the particles currently move
+    /// through.
+    fn draw_overlay(&self, canvas: &Canvas, info: &FrameInfo) {
+        let draw = field_canvas(canvas, info);
+        let rect = Rect::from_corners(Vec2::ZERO, vec2(WIDTH as f32, HEIGHT as f32));
+        self.overlay.draw(&draw, rect, |p| self.field.at(p));
+        if self.overlay.view != FieldView::Off {
+            let label = format!("z = {:.2}", self.field.z_off);
+            canvas.text(&label, vec2(0.0, info.rect.top() - 10.0), 12, BLACK);
+        }
+    }
 }
 
-fn update(_app: &App, model: &mut Model, _update: Update) {
-    model.frame_count += 1;
-    model.update_particles();
-    model.field.update();
+/// Puts (0,0) in the top-left corner and y pointing down, with the field covering the window
+/// minus a margin.
+fn field_canvas(canvas: &Canvas, info: &FrameInfo) -> Canvas {
+    canvas.top_left(info.rect.pad(20.0), vec2(WIDTH as f32, HEIGHT as f32))
 }
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let window = app.window_rect();
-    let boundary = window.pad(20.0);
-
-    let wdraw = app.draw();
-
-    // Puts (0,0) in the top-left corner and y pointing down
-    let draw = wdraw
-        .x_y(-boundary.w() / 2.0, boundary.h() / 2.0)
-        .scale_x(boundary.w() / WIDTH as f32)
-        .scale_y(-boundary.h() / WIDTH as f32);
-    if model.frame_count == 1 {
-        wdraw.background().color(FLORALWHITE);
-        draw.background().color(FLORALWHITE);
-    }
-
-    model.particles.iter().for_each(|part| {
-        draw.path()
-            .stroke()
-            .stroke_weight(0.5)
-            .hsva(0.0, 0.0, 0.05, 0.05)
-            .join_round()
-            .caps_butt()
-            .points(vec![part.prev_pos, part.cur_pos]);
-    });
-
-    draw.to_frame(app, &frame).unwrap();
+#[cfg(test)]
+mod tests {
+    use nannou_playground::preset::Preset;
+
+    use super::*;
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
 }
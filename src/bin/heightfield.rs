@@ -1,79 +1,134 @@
-use nannou::noise::{NoiseFn, Perlin};
+use nannou::noise::{Fbm, NoiseFn};
 use nannou::prelude::*;
+use nannou_playground::{field, palette::Palette, Canvas, FrameInfo, Sketch};
+use serde::{Deserialize, Serialize};
 
 const WIDTH: u32 = 800;
 const HEIGHT: u32 = 800;
+const DEFAULT_PALETTE: &str = "hues";
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou_playground::run::<Model>();
 }
 
 struct Model {
+    seed: u64,
     animate: bool,
-    perlin: Perlin,
+    perlin: Fbm,
+    /// Heights are coloured along it, high ones nearer its start.
+    palette: Palette,
 }
 
-fn model(app: &App) -> Model {
-    let _window = app
-        .new_window()
-        .title(app.exe_name().unwrap())
-        .size(WIDTH, HEIGHT)
-        .view(view)
-        .key_pressed(key_pressed)
-        .build()
-        .unwrap();
-    Model {
-        animate: false,
-        perlin: Perlin::new(),
-    }
+#[derive(Serialize, Deserialize)]
+struct Params {
+    animate: bool,
+    #[serde(default = "default_palette")]
+    palette: String,
 }
 
-fn update(_app: &App, _model: &mut Model, _update: Update) {}
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
+}
+
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [WIDTH, HEIGHT];
+    const BACKGROUND: Srgb<u8> = BLACK;
+
+    type Params = Params;
 
-fn key_pressed(app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::A => model.animate = !model.animate,
-        Key::S => app
-            .main_window()
-            .capture_frame(format!("{}.png", app.exe_name().unwrap())),
-        _ => (),
+    fn new() -> Self {
+        Model {
+            seed: 0,
+            animate: false,
+            perlin: field::perlin(0),
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
+        }
     }
-}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-    let boundary = app.window_rect().pad(100.0);
-    draw.background().color(BLACK);
-
-    let time = if model.animate { app.time } else { 0.0 };
-    let sine = time.sin();
-    let slow_sine = (time / 2.0).sin();
-    let theta = (time / 3.0).sin() * PI * 2.0;
-
-    const STEP: usize = 300;
-    for i in 0..STEP {
-        let x = map_range(i, 0, STEP, boundary.left(), boundary.right());
-        let posx = x / 100.0 + sine;
-        for j in 0..STEP {
-            let y = map_range(j, 0, STEP, boundary.bottom(), boundary.top());
-            let posy = y / 100.0 + slow_sine;
-
-            // rotate
-            let r = vec2(posx, posy).rotate(theta).as_f64();
-
-            // get the height from some Perlin noise
-            let mut height = model.perlin.get([r.x, r.y]) as f32;
-            // add a bit of higher frequency noise for more details
-            height += 0.3 * model.perlin.get([r.x * 8.0, r.y * 8.0]) as f32;
-
-            draw.ellipse()
-                .x(x + y / 2.0)
-                .y(y + height * 25.0)
-                .w(2.0)
-                .h(2.0)
-                .hsva(0.5 - (height / 3.0), 0.8, 0.8, 0.3);
+    fn params(&self) -> Params {
+        Params {
+            animate: self.animate,
+            palette: self.palette.name().to_string(),
         }
     }
 
-    draw.to_frame(app, &frame).unwrap();
+    fn set_params(&mut self, params: Params) {
+        self.animate = params.animate;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+    }
+
+    fn key_pressed(&mut self, key: Key) -> bool {
+        if key == Key::A {
+            self.animate = !self.animate;
+        }
+        false
+    }
+
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.perlin = field::perlin(seed as u32);
+    }
+
+    fn draw(&self, draw: &Canvas, info: &FrameInfo) {
+        let boundary = info.rect.pad(100.0);
+
+        let time = if self.animate { info.time } else { 0.0 };
+        let sine = time.sin();
+        let slow_sine = (time / 2.0).sin();
+        let theta = (time / 3.0).sin() * PI * 2.0;
+
+        const STEP: usize = 300;
+        for i in 0..STEP {
+            let x = map_range(i, 0, STEP, boundary.left(), boundary.right());
+            let posx = x / 100.0 + sine;
+            for j in 0..STEP {
+                let y = map_range(j, 0, STEP, boundary.bottom(), boundary.top());
+                let posy = y / 100.0 + slow_sine;
+
+                // rotate
+                let r = vec2(posx, posy).rotate(theta).as_f64();
+
+                // get the height from some Perlin noise
+                let mut height = self.perlin.get([r.x, r.y]) as f32;
+                // add a bit of higher frequency noise for more details
+                height += 0.3 * self.perlin.get([r.x * 8.0, r.y * 8.0]) as f32;
+
+                let c = self.palette.gradient(0.5 - (height / 3.0));
+                draw.ellipse(
+                    vec2(x + y / 2.0, y + height * 25.0),
+                    vec2(2.0, 2.0),
+                    srgba(c.red, c.green, c.blue, 0.3),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou_playground::preset::Preset;
+
+    use super::*;
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
 }
@@ -1,77 +1,121 @@
 use nannou::prelude::*;
+use nannou_playground::{canvas::Stroke, clock, palette::Palette, Canvas, FrameInfo, Sketch};
+use serde::{Deserialize, Serialize};
+
+// nannou's default window size
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 768;
+/// Seconds for the phase to go around once.
+const PERIOD: f32 = 5.0;
+const DEFAULT_PALETTE: &str = "hues";
+/// How far along the palette the curve's colour is.
+const SHADE: f32 = 0.66;
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou_playground::run::<Model>();
 }
 
 struct Model {
     a: u64,
     b: u64,
+    palette: Palette,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Params {
+    a: u64,
+    b: u64,
+    #[serde(default = "default_palette")]
+    palette: String,
 }
 
-fn model(app: &App) -> Model {
-    let _window = app
-        .new_window()
-        .title(app.exe_name().unwrap())
-        .view(view)
-        .key_pressed(key_pressed)
-        .build();
-    Model { a: 1, b: 2 }
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
 }
 
-fn key_pressed(_app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::Left => {
-            if model.a > 1 {
-                model.a -= 1
-            }
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [WIDTH, HEIGHT];
+    const BACKGROUND: Srgb<u8> = BLACK;
+    const LOOP_FRAMES: Option<u64> = Some((PERIOD * clock::FPS) as u64);
+
+    type Params = Params;
+
+    fn new() -> Self {
+        Model {
+            a: 1,
+            b: 2,
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
         }
-        Key::Right => model.a += 1,
-        Key::Down => {
-            if model.b > 1 {
-                model.b -= 1
-            }
+    }
+
+    fn params(&self) -> Params {
+        Params {
+            a: self.a,
+            b: self.b,
+            palette: self.palette.name().to_string(),
         }
-        Key::Up => model.b += 1,
-        _ => {}
     }
-}
-fn update(_app: &App, _model: &mut Model, _update: Update) {}
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let window = app.window_rect();
-    let boundary = window.pad(20.0);
+    fn set_params(&mut self, params: Params) {
+        self.a = params.a;
+        self.b = params.b;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+    }
 
-    let draw = app.draw();
-    let delta = (app.elapsed_frames() % 300) as f32 / 300.0 * TAU;
+    fn key_pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::Left if self.a > 1 => self.a -= 1,
+            Key::Right => self.a += 1,
+            Key::Down if self.b > 1 => self.b -= 1,
+            Key::Up => self.b += 1,
+            _ => {}
+        }
+        false
+    }
 
-    draw.rect().wh(window.wh()).rgba(0.0, 0.0, 0.0, 1.0);
-    let max_t = TAU / gcd(model.a, model.b) as f32;
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
 
-    const NUM_STEPS: usize = 10000;
-    let mut pts = Vec::with_capacity(NUM_STEPS);
-    for i in 0..=NUM_STEPS {
-        let t = map_range(i, 0, NUM_STEPS, 0.0, max_t);
-        let x = (model.a as f32 * t + delta).sin() * boundary.right();
-        let y = (model.b as f32 * t).sin() * boundary.top();
-        pts.push(vec2(x, y));
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
     }
 
-    draw.path()
-        .stroke()
-        .stroke_weight(1.0)
-        .hsva(0.66, 0.8, 0.8, 0.8)
-        .join_round()
-        .caps_round()
-        .points(pts);
-
-    let s = format!("a={}, b={}", model.a, model.b);
-    draw.text(&s)
-        .xy(boundary.mid_top())
-        .font_size(18)
-        .color(WHITE);
-
-    draw.to_frame(app, &frame).unwrap();
+    fn summary(&self) -> String {
+        format!("a{}-b{}", self.a, self.b)
+    }
+
+    fn draw(&self, draw: &Canvas, info: &FrameInfo) {
+        let window = info.rect;
+        let boundary = window.pad(20.0);
+
+        let delta = (info.time / PERIOD).fract() * TAU;
+
+        let max_t = TAU / gcd(self.a, self.b) as f32;
+
+        const NUM_STEPS: usize = 10000;
+        let mut pts = Vec::with_capacity(NUM_STEPS);
+        for i in 0..=NUM_STEPS {
+            let t = map_range(i, 0, NUM_STEPS, 0.0, max_t);
+            let x = (self.a as f32 * t + delta).sin() * boundary.right();
+            let y = (self.b as f32 * t).sin() * boundary.top();
+            pts.push(vec2(x, y));
+        }
+
+        let c = self.palette.gradient(SHADE);
+        draw.polyline(
+            pts,
+            Stroke::new(1.0, srgba(c.red, c.green, c.blue, 0.8))
+                .join_round()
+                .caps_round(),
+        );
+
+        let s = format!("a={}, b={}", self.a, self.b);
+        draw.text(&s, boundary.mid_top(), 18, WHITE);
+    }
 }
 
 fn gcd(a: u64, b: u64) -> u64 {
@@ -83,3 +127,17 @@ fn gcd(a: u64, b: u64) -> u64 {
         gcd(b, a % b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nannou_playground::preset::Preset;
+
+    use super::*;
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
+}
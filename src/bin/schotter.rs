@@ -4,7 +4,9 @@ use nannou::{
     prelude::*,
     rand::{prelude::StdRng, Rng, SeedableRng},
 };
-use nannou_egui::{self, egui, Egui};
+use nannou_egui::egui;
+use nannou_playground::{canvas::Stroke, palette::Palette, Canvas, FrameInfo, Sketch};
+use serde::{Deserialize, Serialize};
 
 const ROWS: u32 = 22;
 const COLS: u32 = 12;
@@ -13,13 +15,15 @@ const MARGIN: u32 = 35;
 const WIDTH: u32 = COLS * SIZE + 2 * MARGIN;
 const HEIGHT: u32 = ROWS * SIZE + 2 * MARGIN;
 const LINE_WIDTH: f32 = 0.06;
+const DEFAULT_PALETTE: &str = "ink";
 
 struct Model {
-    ui: Egui,
     seed: u64,
     disp_adj: f32,
     rot_adj: f32,
     gravel: Vec<Stone>,
+    /// Stones take its colours in turn.
+    palette: Palette,
 }
 
 struct Stone {
@@ -43,92 +47,147 @@ impl Stone {
 }
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou_playground::run::<Model>();
 }
 
-fn model(app: &App) -> Model {
-    app.set_loop_mode(LoopMode::Wait);
-    let window_id = app
-        .new_window()
-        .title(app.exe_name().unwrap())
-        .view(view)
-        .raw_event(raw_window_event)
-        .size(WIDTH, HEIGHT)
-        .build()
-        .unwrap();
-
-    let window = app.window(window_id).unwrap();
-    let egui = Egui::from_window(&window);
-
-    let mut stones = vec![];
-    for y in 0..ROWS {
-        for x in 0..COLS {
-            stones.push(Stone::new(x as f32, y as f32));
+impl Model {
+    fn update_gravel(&mut self) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        for stone in self.gravel.iter_mut() {
+            // factor that goes from 0 to 1 as we go down the y direction. We use that to increase
+            // the amount of randomness as we go down.
+            let factor = stone.y / ROWS as f32;
+            let x_offset = self.disp_adj * factor * rng.gen_range(-0.5..0.5);
+            let y_offset = self.disp_adj * factor * rng.gen_range(-0.5..0.5);
+            let rotation = self.rot_adj * factor * rng.gen_range(-FRAC_PI_4..FRAC_PI_4);
+            stone.x_offset = x_offset;
+            stone.y_offset = y_offset;
+            stone.rotation = rotation;
         }
     }
+}
 
-    Model {
-        ui: egui,
-        seed: 0,
-        disp_adj: 1.0,
-        rot_adj: 1.0,
-        gravel: stones,
-    }
+#[derive(Serialize, Deserialize)]
+struct Params {
+    disp_adj: f32,
+    rot_adj: f32,
+    #[serde(default = "default_palette")]
+    palette: String,
+}
+
+fn default_palette() -> String {
+    DEFAULT_PALETTE.to_string()
 }
 
-fn update(_app: &App, model: &mut Model, update: Update) {
-    // UI
-    model.ui.set_elapsed_time(update.since_start);
-    let ctx = model.ui.begin_frame();
-    egui::Window::new("Settings").show(&ctx, |ui| {
-        ui.vertical_centered(|ui| {
-            ui.add(egui::Slider::new(&mut model.disp_adj, 0.0..=5.0).text("Displacement"));
-            ui.add(egui::Slider::new(&mut model.rot_adj, 0.0..=5.0).text("Rotation"));
-            if ui.button("Randomize").clicked() {
-                model.seed = random_range(0, 1000000);
+impl Sketch for Model {
+    const SIZE: [u32; 2] = [WIDTH, HEIGHT];
+
+    type Params = Params;
+
+    fn new() -> Self {
+        let mut stones = vec![];
+        for y in 0..ROWS {
+            for x in 0..COLS {
+                stones.push(Stone::new(x as f32, y as f32));
             }
-        })
-    });
-
-    let mut rng = StdRng::seed_from_u64(model.seed);
-    for stone in model.gravel.iter_mut() {
-        // factor that goes from 0 to 1 as we go down the y direction. We use that to increase
-        // the amount of randomness as we go down.
-        let factor = stone.y / ROWS as f32;
-        let x_offset = model.disp_adj * factor * rng.gen_range(-0.5..0.5);
-        let y_offset = model.disp_adj * factor * rng.gen_range(-0.5..0.5);
-        let rotation = model.rot_adj * factor * rng.gen_range(-FRAC_PI_4..FRAC_PI_4);
-        stone.x_offset = x_offset;
-        stone.y_offset = y_offset;
-        stone.rotation = rotation;
+        }
+
+        Model {
+            seed: 0,
+            disp_adj: 1.0,
+            rot_adj: 1.0,
+            gravel: stones,
+            palette: Palette::named(DEFAULT_PALETTE).unwrap(),
+        }
     }
-}
 
-fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::event::WindowEvent) {
-    model.ui.handle_raw_event(event);
-}
+    fn params(&self) -> Params {
+        Params {
+            disp_adj: self.disp_adj,
+            rot_adj: self.rot_adj,
+            palette: self.palette.name().to_string(),
+        }
+    }
+
+    fn set_params(&mut self, params: Params) {
+        self.disp_adj = params.disp_adj;
+        self.rot_adj = params.rot_adj;
+        match Palette::named(&params.palette) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => println!("Keeping palette {}: {}", self.palette.name(), e),
+        }
+    }
+
+    fn loop_mode() -> LoopMode {
+        LoopMode::Wait
+    }
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-    // "Grid" coordinate system. Origin is at the center of the top-left square, with y going down
-    let gdraw = draw
-        .scale(SIZE as f32)
-        .scale_y(-1.0)
-        .x_y(COLS as f32 / -2.0 + 0.5, ROWS as f32 / -2.0 + 0.5);
-
-    draw.background().color(FLORALWHITE);
-
-    for stone in &model.gravel {
-        gdraw
-            .rect()
-            .no_fill()
-            .stroke(BLACK)
-            .stroke_weight(LINE_WIDTH)
-            .w_h(1.0, 1.0)
-            .x_y(stone.x + stone.x_offset, stone.y + stone.y_offset)
-            .rotate(stone.rotation);
+    fn update(&mut self, _info: &FrameInfo) {
+        self.update_gravel();
     }
 
-    draw.to_frame(app, &frame).unwrap();
-    model.ui.draw_to_frame(&frame).unwrap();
+    fn palette(&self) -> Option<&Palette> {
+        Some(&self.palette)
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn summary(&self) -> String {
+        format!("disp{:.2}-rot{:.2}", self.disp_adj, self.rot_adj)
+    }
+
+    fn ui(&mut self, ctx: &egui::Context) -> bool {
+        egui::Window::new("Settings").show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add(egui::Slider::new(&mut self.disp_adj, 0.0..=5.0).text("Displacement"));
+                ui.add(egui::Slider::new(&mut self.rot_adj, 0.0..=5.0).text("Rotation"));
+                if ui.button("Randomize").clicked() {
+                    self.reseed(nannou_playground::sketch::random_seed());
+                }
+            })
+        });
+        false
+    }
+
+    fn draw(&self, draw: &Canvas, _info: &FrameInfo) {
+        // "Grid" coordinate system. Origin is at the center of the top-left square, with y going down
+        let gdraw = draw
+            .scale(SIZE as f32)
+            .scale_y(-1.0)
+            .x_y(COLS as f32 / -2.0 + 0.5, ROWS as f32 / -2.0 + 0.5);
+
+        let colors = self.palette.colors();
+        for (i, stone) in self.gravel.iter().enumerate() {
+            gdraw.rect(
+                vec2(stone.x + stone.x_offset, stone.y + stone.y_offset),
+                vec2(1.0, 1.0),
+                stone.rotation,
+                Stroke::new(LINE_WIDTH, colors[i % colors.len()]),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou_playground::preset::Preset;
+
+    use super::*;
+
+    #[test]
+    fn default_params_round_trip_as_toml() {
+        let text = toml::to_string_pretty(&Preset::of(&Model::new())).unwrap();
+        let preset: Preset<Params> = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&preset).unwrap(), text);
+    }
 }
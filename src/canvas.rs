@@ -0,0 +1,332 @@
+//! A small retained drawing API mirroring the parts of `nannou::Draw` the sketches use.
+//!
+//! Sketches record their shapes into a `Canvas`, which can then be replayed into a
+//! `nannou::Draw` for the window, or handed to one of the offline backends.
+
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use nannou::{
+    color::{IntoLinSrgba, LinSrgba},
+    image::{DynamicImage, RgbaImage},
+    prelude::*,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cap {
+    Butt,
+    Round,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Join {
+    Miter,
+    Round,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stroke {
+    pub weight: f32,
+    pub color: LinSrgba,
+    pub cap: Cap,
+    pub join: Join,
+}
+
+impl Stroke {
+    /// Same defaults as nannou: butt caps and miter joins.
+    pub fn new<C: IntoLinSrgba<f32>>(weight: f32, color: C) -> Self {
+        Stroke {
+            weight,
+            color: color.into_lin_srgba(),
+            cap: Cap::Butt,
+            join: Join::Miter,
+        }
+    }
+
+    pub fn caps_round(mut self) -> Self {
+        self.cap = Cap::Round;
+        self
+    }
+
+    pub fn join_round(mut self) -> Self {
+        self.join = Join::Round;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Shape {
+    /// Clears the whole target, ignoring the transform.
+    Background(LinSrgba),
+    Polyline {
+        points: Vec<Vec2>,
+        stroke: Stroke,
+    },
+    /// A filled polygon, closed from its last point back to its first.
+    Polygon {
+        points: Vec<Vec2>,
+        color: LinSrgba,
+    },
+    Ellipse {
+        center: Vec2,
+        size: Vec2,
+        color: LinSrgba,
+    },
+    /// An unfilled rectangle, rotated around its center.
+    Rect {
+        center: Vec2,
+        size: Vec2,
+        rotation: f32,
+        stroke: Stroke,
+    },
+    /// A single line of text centered on `position`.
+    Text {
+        text: String,
+        position: Vec2,
+        size: u32,
+        color: LinSrgba,
+    },
+    /// A bitmap stretched over `rect`, with straight (not premultiplied) sRGB pixels.
+    Image {
+        rect: Rect,
+        image: Rc<RgbaImage>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Item {
+    pub transform: Affine2,
+    pub shape: Shape,
+}
+
+/// Records shapes along with the transform they were drawn with.
+///
+/// Like `nannou::Draw`, transforming a `Canvas` gives a new handle sharing the same list of
+/// shapes.
+#[derive(Clone, Debug, Default)]
+pub struct Canvas {
+    items: Rc<RefCell<Vec<Item>>>,
+    transform: Affine2,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all recorded shapes.
+    pub fn reset(&self) {
+        self.items.borrow_mut().clear();
+    }
+
+    pub fn items(&self) -> Ref<'_, Vec<Item>> {
+        self.items.borrow()
+    }
+
+    // Transforms
+
+    pub fn transform(&self, transform: Affine2) -> Self {
+        Canvas {
+            items: self.items.clone(),
+            transform: self.transform * transform,
+        }
+    }
+
+    pub fn x_y(&self, x: f32, y: f32) -> Self {
+        self.transform(Affine2::from_translation(vec2(x, y)))
+    }
+
+    pub fn scale(&self, s: f32) -> Self {
+        self.transform(Affine2::from_scale(vec2(s, s)))
+    }
+
+    pub fn scale_x(&self, s: f32) -> Self {
+        self.transform(Affine2::from_scale(vec2(s, 1.0)))
+    }
+
+    pub fn scale_y(&self, s: f32) -> Self {
+        self.transform(Affine2::from_scale(vec2(1.0, s)))
+    }
+
+    /// Map `area` to a `size.x` by `size.y` coordinate system, with the origin in the top-left
+    /// corner of `area` and y pointing down.
+    pub fn top_left(&self, area: Rect, size: Vec2) -> Self {
+        self.x_y(area.left(), area.top())
+            .scale_x(area.w() / size.x)
+            .scale_y(-area.h() / size.y)
+    }
+
+    // Shapes
+
+    fn push(&self, shape: Shape) {
+        self.items.borrow_mut().push(Item {
+            transform: self.transform,
+            shape,
+        });
+    }
+
+    pub fn background<C: IntoLinSrgba<f32>>(&self, color: C) {
+        self.push(Shape::Background(color.into_lin_srgba()));
+    }
+
+    pub fn polyline<I>(&self, points: I, stroke: Stroke)
+    where
+        I: IntoIterator<Item = Vec2>,
+    {
+        self.push(Shape::Polyline {
+            points: points.into_iter().collect(),
+            stroke,
+        });
+    }
+
+    pub fn polygon<I, C>(&self, points: I, color: C)
+    where
+        I: IntoIterator<Item = Vec2>,
+        C: IntoLinSrgba<f32>,
+    {
+        self.push(Shape::Polygon {
+            points: points.into_iter().collect(),
+            color: color.into_lin_srgba(),
+        });
+    }
+
+    pub fn ellipse<C: IntoLinSrgba<f32>>(&self, center: Vec2, size: Vec2, color: C) {
+        self.push(Shape::Ellipse {
+            center,
+            size,
+            color: color.into_lin_srgba(),
+        });
+    }
+
+    pub fn rect(&self, center: Vec2, size: Vec2, rotation: f32, stroke: Stroke) {
+        self.push(Shape::Rect {
+            center,
+            size,
+            rotation,
+            stroke,
+        });
+    }
+
+    pub fn text<C: IntoLinSrgba<f32>>(&self, text: &str, position: Vec2, size: u32, color: C) {
+        self.push(Shape::Text {
+            text: text.to_string(),
+            position,
+            size,
+            color: color.into_lin_srgba(),
+        });
+    }
+
+    pub fn image(&self, rect: Rect, image: RgbaImage) {
+        self.push(Shape::Image {
+            rect,
+            image: Rc::new(image),
+        });
+    }
+
+    // Backends
+
+    /// Replay all recorded shapes into the given `Draw`. Images are uploaded to textures of the
+    /// app's device.
+    pub fn to_draw(&self, app: &App, draw: &Draw) {
+        for item in self.items().iter() {
+            let d = draw.transform(to_mat4(item.transform));
+            match &item.shape {
+                Shape::Background(color) => {
+                    draw.background().color(*color);
+                }
+                Shape::Polyline { points, stroke } => {
+                    let path = d.path().stroke().stroke_weight(stroke.weight);
+                    let path = match stroke.cap {
+                        Cap::Butt => path.caps_butt(),
+                        Cap::Round => path.caps_round(),
+                    };
+                    let path = match stroke.join {
+                        Join::Miter => path.join_miter(),
+                        Join::Round => path.join_round(),
+                    };
+                    path.color(stroke.color).points(points.iter().copied());
+                }
+                Shape::Polygon { points, color } => {
+                    d.polygon().color(*color).points(points.iter().copied());
+                }
+                Shape::Ellipse {
+                    center,
+                    size,
+                    color,
+                } => {
+                    d.ellipse().xy(*center).wh(*size).color(*color);
+                }
+                Shape::Rect {
+                    center,
+                    size,
+                    rotation,
+                    stroke,
+                } => {
+                    d.rect()
+                        .no_fill()
+                        .stroke(stroke.color)
+                        .stroke_weight(stroke.weight)
+                        .wh(*size)
+                        .xy(*center)
+                        .rotate(*rotation);
+                }
+                Shape::Text {
+                    text,
+                    position,
+                    size,
+                    color,
+                } => {
+                    d.text(text).xy(*position).font_size(*size).color(*color);
+                }
+                Shape::Image { rect, image } => {
+                    let image = DynamicImage::ImageRgba8((**image).clone());
+                    let texture = wgpu::Texture::from_image(app, &image);
+                    d.texture(&texture).xy(rect.xy()).wh(rect.wh());
+                }
+            }
+        }
+    }
+
+    /// Draw the recorded shapes to the given window frame.
+    pub fn to_frame(&self, app: &App, frame: &Frame) {
+        let draw = app.draw();
+        self.to_draw(app, &draw);
+        draw.to_frame(app, frame).unwrap();
+    }
+}
+
+fn to_mat4(t: Affine2) -> Mat4 {
+    Mat4::from_cols(
+        t.x_axis.extend(0.0).extend(0.0),
+        t.y_axis.extend(0.0).extend(0.0),
+        Vec4::Z,
+        t.translation.extend(0.0).extend(1.0),
+    )
+}
+
+/// Convert a recorded colour back to (gamma-encoded) sRGB, as expected by image formats.
+pub fn to_srgba(color: LinSrgba) -> Srgba {
+    Srgba::from_linear(color)
+}
+
+/// Maps the pixels of `image`, from the top left, to `rect`.
+pub fn image_transform(rect: Rect, image: &RgbaImage) -> Affine2 {
+    Affine2::from_translation(vec2(rect.left(), rect.top()))
+        * Affine2::from_scale(vec2(
+            rect.w() / image.width() as f32,
+            -rect.h() / image.height() as f32,
+        ))
+}
+
+/// Corners of a rectangle rotated around its center, in drawing order.
+pub fn rect_corners(center: Vec2, size: Vec2, rotation: f32) -> [Vec2; 4] {
+    [
+        vec2(-0.5, -0.5),
+        vec2(0.5, -0.5),
+        vec2(0.5, 0.5),
+        vec2(-0.5, 0.5),
+    ]
+    .map(|c| center + (c * size).rotate(rotation))
+}
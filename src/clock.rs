@@ -0,0 +1,149 @@
+//! The time sketches see, decoupled from the wall clock so animations can be rendered
+//! deterministically.
+
+use nannou::prelude::*;
+
+use crate::FrameInfo;
+
+/// Frame rate of the fixed timestep.
+pub const FPS: f32 = 60.0;
+
+/// How the clock advances with each frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timing {
+    /// Follow the wall clock, however long frames actually take.
+    RealTime,
+    /// Exactly `1 / FPS` seconds per frame, independent of how fast frames are rendered.
+    Fixed,
+}
+
+/// Counts frames and time since the sketch last (re)started.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    timing: Timing,
+    frame: u64,
+    time: f32,
+    paused: bool,
+}
+
+impl Clock {
+    pub fn new(timing: Timing) -> Self {
+        Clock {
+            timing,
+            frame: 0,
+            time: 0.0,
+            paused: false,
+        }
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    /// Frames since the sketch last (re)started, starting at 1 for the first frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Seconds since the sketch last (re)started.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.time = 0.0;
+    }
+
+    /// Advance by one frame that took `elapsed` seconds of real time, unless paused. Returns
+    /// whether the clock advanced.
+    pub fn tick(&mut self, elapsed: f32) -> bool {
+        if self.paused {
+            return false;
+        }
+        self.advance(elapsed);
+        true
+    }
+
+    /// Advance by exactly one fixed step, even when paused.
+    pub fn step(&mut self) {
+        self.advance(1.0 / FPS);
+    }
+
+    /// Time only ever moves on by a frame's worth, so switching timing doesn't make it jump.
+    fn advance(&mut self, elapsed: f32) {
+        self.frame += 1;
+        self.time += match self.timing {
+            Timing::RealTime => elapsed,
+            Timing::Fixed => 1.0 / FPS,
+        };
+    }
+
+    /// Describe the current frame, drawn to `rect` at `scale` pixels per unit.
+    pub fn info(&self, rect: Rect, scale: f32) -> FrameInfo {
+        FrameInfo {
+            rect,
+            frame: self.frame,
+            time: self.time,
+            scale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_time(clock: &Clock, seconds: f32) {
+        assert!((clock.time() - seconds).abs() < 1e-4, "{} isn't {}", clock.time(), seconds);
+    }
+
+    #[test]
+    fn fixed_steps_ignore_the_wall_clock() {
+        let mut clock = Clock::new(Timing::Fixed);
+        for _ in 0..30 {
+            assert!(clock.tick(1.0));
+        }
+        assert_eq!(clock.frame(), 30);
+        assert_time(&clock, 0.5);
+    }
+
+    #[test]
+    fn pausing_only_stops_ticks() {
+        let mut clock = Clock::new(Timing::RealTime);
+        clock.tick(0.1);
+        clock.toggle_pause();
+        assert!(!clock.tick(0.1));
+        assert_eq!(clock.frame(), 1);
+        clock.step();
+        assert_eq!(clock.frame(), 2);
+        assert_time(&clock, 0.1 + 1.0 / FPS);
+        clock.toggle_pause();
+        assert!(clock.tick(0.1));
+        assert_time(&clock, 0.2 + 1.0 / FPS);
+    }
+
+    #[test]
+    fn switching_timing_keeps_time_going() {
+        let mut clock = Clock::new(Timing::RealTime);
+        clock.tick(2.0);
+        clock.set_timing(Timing::Fixed);
+        clock.tick(2.0);
+        assert_time(&clock, 2.0 + 1.0 / FPS);
+        clock.restart();
+        assert_eq!(clock.frame(), 0);
+        assert_time(&clock, 0.0);
+    }
+}
@@ -0,0 +1,412 @@
+//! Vector fields built from layers of simple sources, e.g. noise, vortices or images, so that
+//! sketches can share one field description that presets can change.
+//!
+//! A [`Field`] only describes the field; [`Field::sampler`] seeds its noises and loads its
+//! images into a [`Sampler`] that evaluates it.
+
+use std::{error::Error, path::PathBuf};
+
+use nannou::{
+    noise::{Fbm, MultiFractal, NoiseFn, OpenSimplex, Seedable, Worley},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::image_map::ImageMap;
+
+/// A vector field as saved in presets: the weighted sum of its layers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Field {
+    pub layers: Vec<Layer>,
+}
+
+impl Field {
+    /// A field of a single layer.
+    pub fn new(source: Source) -> Self {
+        Field {
+            layers: vec![Layer {
+                source,
+                weight: 1.0,
+                mask: None,
+            }],
+        }
+    }
+
+    /// Prepare the field for sampling over `rect`, which images are stretched over. With
+    /// `y_down`, images are the right way up for coordinates with y pointing down.
+    pub fn sampler(&self, seed: u64, rect: Rect, y_down: bool) -> Result<Sampler, Box<dyn Error>> {
+        let layers = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| {
+                // Every layer gets its own noise, unless they share the seed on purpose
+                let seed = (seed as u32).wrapping_add(i as u32);
+                let mask = match &layer.mask {
+                    Some(mask) => Some(mask.build(rect)?),
+                    None => None,
+                };
+                Ok(LayerSampler {
+                    source: layer.source.build(seed, rect)?,
+                    weight: layer.weight,
+                    mask,
+                })
+            })
+            .collect::<Result<_, Box<dyn Error>>>()?;
+        Ok(Sampler {
+            layers,
+            rect,
+            y_down,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layer {
+    #[serde(flatten)]
+    pub source: Source,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Where the layer applies, fully where the mask is 1 and not at all where it is 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<Mask>,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// Where the vectors of a layer come from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Source {
+    /// Unit vectors at angles given by noise, `turns` full turns over its range.
+    Noise {
+        #[serde(flatten)]
+        noise: Noise,
+        #[serde(default = "default_turns")]
+        turns: f32,
+    },
+    /// Along the contours of noise, so the flow never bunches up or spreads out.
+    Curl {
+        #[serde(flatten)]
+        noise: Noise,
+    },
+    /// Towards `center`, or away from it for negative strengths, fading with distance beyond
+    /// `radius`.
+    Point {
+        center: [f32; 2],
+        radius: f32,
+        #[serde(default = "default_weight")]
+        strength: f32,
+    },
+    /// Around `center`, counter-clockwise (when y points up) for positive strengths, fading
+    /// with distance beyond `radius`.
+    Vortex {
+        center: [f32; 2],
+        radius: f32,
+        #[serde(default = "default_weight")]
+        strength: f32,
+    },
+    /// Up the brightness gradient of an image, or along its edges with `contours`.
+    Image {
+        path: PathBuf,
+        #[serde(default)]
+        contours: bool,
+    },
+    /// The same vector everywhere.
+    Uniform { direction: [f32; 2] },
+}
+
+fn default_turns() -> f32 {
+    1.0
+}
+
+impl Source {
+    fn build(&self, seed: u32, rect: Rect) -> Result<SourceSampler, Box<dyn Error>> {
+        Ok(match self {
+            Source::Noise { noise, turns } => SourceSampler::Noise(noise.build(seed), *turns),
+            Source::Curl { noise } => SourceSampler::Curl(noise.build(seed)),
+            Source::Point {
+                center,
+                radius,
+                strength,
+            } => SourceSampler::Point(Vec2::from(*center), *radius, *strength),
+            Source::Vortex {
+                center,
+                radius,
+                strength,
+            } => SourceSampler::Vortex(Vec2::from(*center), *radius, *strength),
+            Source::Image { path, contours } => {
+                SourceSampler::Image(ImageMap::open(path, rect)?, *contours)
+            }
+            Source::Uniform { direction } => SourceSampler::Uniform(Vec2::from(*direction)),
+        })
+    }
+}
+
+/// Perlin noise. `noise` 0.7 exports two different types named `Perlin`, which newer
+/// compilers refuse to pick between, so this is a single octave of its fBm instead, which is
+/// the same noise.
+pub fn perlin(seed: u32) -> Fbm {
+    Fbm::new().set_octaves(1).set_seed(seed)
+}
+
+/// Fractal noise: `octaves` layers of noise, each `lacunarity` times finer and `gain` times
+/// weaker than the one before, scaled to the range of a single layer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Noise {
+    #[serde(default)]
+    pub noise: NoiseKind,
+    /// Features per unit, e.g. per pixel.
+    pub frequency: f64,
+    #[serde(default = "default_octaves")]
+    pub octaves: u32,
+    #[serde(default = "default_lacunarity")]
+    pub lacunarity: f64,
+    #[serde(default = "default_gain")]
+    pub gain: f64,
+}
+
+fn default_octaves() -> u32 {
+    1
+}
+
+fn default_lacunarity() -> f64 {
+    2.0
+}
+
+fn default_gain() -> f64 {
+    0.5
+}
+
+impl Noise {
+    /// A single octave of `kind` noise.
+    pub fn new(kind: NoiseKind, frequency: f64) -> Self {
+        Noise {
+            noise: kind,
+            frequency,
+            octaves: default_octaves(),
+            lacunarity: default_lacunarity(),
+            gain: default_gain(),
+        }
+    }
+
+    fn build(&self, seed: u32) -> NoiseSampler {
+        let noise: Box<dyn NoiseFn<[f64; 3]> + Send + Sync> = match self.noise {
+            NoiseKind::Perlin => Box::new(perlin(seed)),
+            NoiseKind::Simplex => Box::new(OpenSimplex::new().set_seed(seed)),
+            NoiseKind::Worley => Box::new(Worley::new().set_seed(seed)),
+        };
+        NoiseSampler {
+            noise,
+            params: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseKind {
+    #[default]
+    Perlin,
+    Simplex,
+    /// Cells of constant value.
+    Worley,
+}
+
+/// How much of a layer applies where.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Mask {
+    /// 1 inside the circle, fading out over `feather` beyond it.
+    Circle {
+        center: [f32; 2],
+        radius: f32,
+        #[serde(default)]
+        feather: f32,
+        #[serde(default)]
+        invert: bool,
+    },
+    /// The brightness of an image.
+    Image {
+        path: PathBuf,
+        #[serde(default)]
+        invert: bool,
+    },
+}
+
+impl Mask {
+    fn build(&self, rect: Rect) -> Result<MaskSampler, Box<dyn Error>> {
+        Ok(match self {
+            Mask::Circle {
+                center,
+                radius,
+                feather,
+                invert,
+            } => MaskSampler::Circle(Vec2::from(*center), *radius, *feather, *invert),
+            Mask::Image { path, invert } => {
+                MaskSampler::Image(ImageMap::open(path, rect)?, *invert)
+            }
+        })
+    }
+}
+
+/// A field ready to be evaluated.
+pub struct Sampler {
+    layers: Vec<LayerSampler>,
+    rect: Rect,
+    y_down: bool,
+}
+
+impl Sampler {
+    /// The vector at `p`, in the slice `z` of any noise, which can be moved through to
+    /// animate the field.
+    pub fn at(&self, p: Vec2, z: f64) -> Vec2 {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let mask = match &layer.mask {
+                    Some(mask) => self.mask(mask, p),
+                    None => 1.0,
+                };
+                if mask <= 0.0 {
+                    return Vec2::ZERO;
+                }
+                self.source(&layer.source, p, z) * layer.weight * mask
+            })
+            .fold(Vec2::ZERO, |sum, v| sum + v)
+    }
+
+    /// Where `p` is on an image that is the right way up.
+    fn image_point(&self, p: Vec2) -> Vec2 {
+        if self.y_down {
+            vec2(p.x, self.rect.top() + self.rect.bottom() - p.y)
+        } else {
+            p
+        }
+    }
+
+    fn source(&self, source: &SourceSampler, p: Vec2, z: f64) -> Vec2 {
+        match source {
+            SourceSampler::Noise(noise, turns) => {
+                let angle = TAU * turns * noise.get(p, z) as f32;
+                vec2(angle.sin(), angle.cos())
+            }
+            SourceSampler::Curl(noise) => {
+                let g = noise.gradient(p, z);
+                vec2(g.y, -g.x)
+            }
+            SourceSampler::Point(center, radius, strength) => {
+                let d = *center - p;
+                d.normalize_or_zero() * *strength * falloff(d.length(), *radius)
+            }
+            SourceSampler::Vortex(center, radius, strength) => {
+                let d = p - *center;
+                d.perp().normalize_or_zero() * *strength * falloff(d.length(), *radius)
+            }
+            SourceSampler::Image(image, contours) => {
+                // Central differences a pixel apart, scaled so a ramp from black to white
+                // across the whole image has a gradient of length 1
+                let q = self.image_point(p);
+                let e = image.pixel_size();
+                let dx = image.luma(q + vec2(e.x, 0.0)) - image.luma(q - vec2(e.x, 0.0));
+                let dy = image.luma(q + vec2(0.0, e.y)) - image.luma(q - vec2(0.0, e.y));
+                let mut g = vec2(dx, dy) * image.rect().wh() / e / 2.0;
+                if self.y_down {
+                    g.y = -g.y;
+                }
+                if *contours {
+                    g.perp()
+                } else {
+                    g
+                }
+            }
+            SourceSampler::Uniform(v) => *v,
+        }
+    }
+
+    fn mask(&self, mask: &MaskSampler, p: Vec2) -> f32 {
+        let (value, invert) = match mask {
+            MaskSampler::Circle(center, radius, feather, invert) => {
+                let outside = p.distance(*center) - radius;
+                let value = if outside <= 0.0 {
+                    1.0
+                } else if *feather > 0.0 {
+                    (1.0 - outside / feather).max(0.0)
+                } else {
+                    0.0
+                };
+                (value, *invert)
+            }
+            MaskSampler::Image(image, invert) => (image.luma(self.image_point(p)), *invert),
+        };
+        if invert {
+            1.0 - value
+        } else {
+            value
+        }
+    }
+}
+
+/// 1 within `radius`, then fading with the square of the distance.
+fn falloff(d: f32, radius: f32) -> f32 {
+    if d <= radius {
+        1.0
+    } else {
+        (radius / d).powi(2)
+    }
+}
+
+struct LayerSampler {
+    source: SourceSampler,
+    weight: f32,
+    mask: Option<MaskSampler>,
+}
+
+enum SourceSampler {
+    Noise(NoiseSampler, f32),
+    Curl(NoiseSampler),
+    Point(Vec2, f32, f32),
+    Vortex(Vec2, f32, f32),
+    Image(ImageMap, bool),
+    Uniform(Vec2),
+}
+
+enum MaskSampler {
+    Circle(Vec2, f32, f32, bool),
+    Image(ImageMap, bool),
+}
+
+struct NoiseSampler {
+    noise: Box<dyn NoiseFn<[f64; 3]> + Send + Sync>,
+    params: Noise,
+}
+
+impl NoiseSampler {
+    fn get(&self, p: Vec2, z: f64) -> f64 {
+        let Noise {
+            frequency,
+            octaves,
+            lacunarity,
+            gain,
+            ..
+        } = self.params;
+        let (mut sum, mut total, mut amplitude, mut scale) = (0.0, 0.0, 1.0, frequency);
+        for _ in 0..octaves.max(1) {
+            sum += amplitude * self.noise.get([p.x as f64 * scale, p.y as f64 * scale, z]);
+            total += amplitude;
+            amplitude *= gain;
+            scale *= lacunarity;
+        }
+        sum / total
+    }
+
+    /// The gradient of the noise with respect to noise coordinates, i.e. ignoring frequency.
+    fn gradient(&self, p: Vec2, z: f64) -> Vec2 {
+        let e = (1e-3 / self.params.frequency) as f32;
+        let dx = self.get(p + vec2(e, 0.0), z) - self.get(p - vec2(e, 0.0), z);
+        let dy = self.get(p + vec2(0.0, e), z) - self.get(p - vec2(0.0, e), z);
+        vec2(dx as f32, dy as f32) / 2e-3
+    }
+}
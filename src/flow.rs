@@ -0,0 +1,237 @@
+//! Vector fields stored on a grid, which can be sampled anywhere in between and followed to
+//! trace flow lines.
+
+use nannou::prelude::*;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// How vectors between the nodes of a field are computed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    /// The vector of the closest node, which looks blocky up close.
+    Nearest,
+    /// Blend the four surrounding nodes.
+    #[default]
+    Bilinear,
+    /// Catmull-Rom splines through the sixteen surrounding nodes, smooth across nodes too.
+    Bicubic,
+}
+
+/// How flow lines are stepped along the field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Integrator {
+    /// Straight ahead in the direction at the start of the step, which cuts corners.
+    Euler,
+    /// The direction at the middle of the step.
+    Rk2,
+    /// A blend of four directions along the step, accurate even with long steps.
+    #[default]
+    Rk4,
+}
+
+/// Vectors at the nodes of a grid spanning `rect`, the outermost nodes lying on its edges.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    rect: Rect,
+    /// Indexed by column and row, starting from the bottom left corner of `rect`.
+    vectors: Array2<Vec2>,
+    interpolation: Interpolation,
+}
+
+impl FlowField {
+    /// A field of `cols` × `rows` nodes, each with the vector `f` gives for its position.
+    pub fn from_fn(rect: Rect, cols: usize, rows: usize, f: impl Fn(Vec2) -> Vec2) -> Self {
+        assert!(cols >= 2 && rows >= 2, "fields need at least 2×2 nodes");
+        let mut field = FlowField {
+            rect,
+            vectors: Array2::from_elem((cols, rows), Vec2::ZERO),
+            interpolation: Interpolation::default(),
+        };
+        for col in 0..cols {
+            for row in 0..rows {
+                field.vectors[(col, row)] = f(field.node(col, row));
+            }
+        }
+        field
+    }
+
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn cols(&self) -> usize {
+        self.vectors.dim().0
+    }
+
+    pub fn rows(&self) -> usize {
+        self.vectors.dim().1
+    }
+
+    pub fn vectors(&self) -> &Array2<Vec2> {
+        &self.vectors
+    }
+
+    /// The position of a node.
+    pub fn node(&self, col: usize, row: usize) -> Vec2 {
+        let spacing = self.rect.wh() / vec2(self.cols() as f32 - 1.0, self.rows() as f32 - 1.0);
+        self.rect.bottom_left() + vec2(col as f32, row as f32) * spacing
+    }
+
+    fn vector(&self, col: isize, row: isize) -> Vec2 {
+        let col = col.clamp(0, self.cols() as isize - 1) as usize;
+        let row = row.clamp(0, self.rows() as isize - 1) as usize;
+        self.vectors[(col, row)]
+    }
+
+    /// The vector at `p`, interpolated between nodes. Points outside the field take the vector
+    /// of the nearest edge.
+    pub fn sample(&self, p: Vec2) -> Vec2 {
+        let max = vec2(self.cols() as f32 - 1.0, self.rows() as f32 - 1.0);
+        let g = ((p - self.rect.bottom_left()) / self.rect.wh() * max).clamp(Vec2::ZERO, max);
+        let (i, j) = (g.x.floor() as isize, g.y.floor() as isize);
+        let f = g - g.floor();
+        match self.interpolation {
+            Interpolation::Nearest => self.vector(g.x.round() as isize, g.y.round() as isize),
+            Interpolation::Bilinear => {
+                let bottom = self.vector(i, j).lerp(self.vector(i + 1, j), f.x);
+                let top = self.vector(i, j + 1).lerp(self.vector(i + 1, j + 1), f.x);
+                bottom.lerp(top, f.y)
+            }
+            Interpolation::Bicubic => {
+                let row = |j| {
+                    let v = |di| self.vector(i + di, j);
+                    catmull_rom(v(-1), v(0), v(1), v(2), f.x)
+                };
+                catmull_rom(row(j - 1), row(j), row(j + 1), row(j + 2), f.y)
+            }
+        }
+    }
+
+    /// The unit vector along the field at `p`, or `None` where the field vanishes.
+    pub fn direction(&self, p: Vec2) -> Option<Vec2> {
+        self.sample(p).try_normalize()
+    }
+
+    /// Where moving `h` along the field from `p` ends up, or `None` if the field vanishes on
+    /// the way. Negative `h` moves against the field.
+    pub fn step(&self, p: Vec2, h: f32, integrator: Integrator) -> Option<Vec2> {
+        let k1 = self.direction(p)?;
+        let delta = match integrator {
+            Integrator::Euler => k1,
+            Integrator::Rk2 => self.direction(p + k1 * h / 2.0)?,
+            Integrator::Rk4 => {
+                let k2 = self.direction(p + k1 * h / 2.0)?;
+                let k3 = self.direction(p + k2 * h / 2.0)?;
+                let k4 = self.direction(p + k3 * h)?;
+                (k1 + 2.0 * k2 + 2.0 * k3 + k4) / 6.0
+            }
+        };
+        Some(p + delta * h)
+    }
+
+    /// Follow the field from `start` for up to `steps` steps of length `h`, stopping early if
+    /// the line leaves the field or the field vanishes.
+    pub fn trace(&self, start: Vec2, steps: usize, h: f32, integrator: Integrator) -> Vec<Vec2> {
+        let mut pts = Vec::with_capacity(steps);
+        let mut p = start;
+        while pts.len() < steps && self.rect.contains(p) {
+            pts.push(p);
+            match self.step(p, h, integrator) {
+                Some(next) => p = next,
+                None => break,
+            }
+        }
+        pts
+    }
+}
+
+/// The Catmull-Rom spline through `b` and `c` at `t`, with `a` and `d` setting the slopes.
+fn catmull_rom(a: Vec2, b: Vec2, c: Vec2, d: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * b
+        + (c - a) * t
+        + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+        + (3.0 * b - a - 3.0 * c + d) * t3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Interpolation; 3] = [
+        Interpolation::Nearest,
+        Interpolation::Bilinear,
+        Interpolation::Bicubic,
+    ];
+
+    fn field(f: impl Fn(Vec2) -> Vec2, interpolation: Interpolation) -> FlowField {
+        FlowField::from_fn(Rect::from_w_h(100.0, 100.0), 11, 11, f)
+            .with_interpolation(interpolation)
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{} isn't {}", a, b);
+    }
+
+    #[test]
+    fn constant_fields_stay_constant() {
+        let v = vec2(0.3, -2.0);
+        for interpolation in ALL {
+            let field = field(|_| v, interpolation);
+            for p in [
+                vec2(-50.0, -50.0),
+                vec2(3.7, 12.1),
+                vec2(49.0, -33.3),
+                vec2(80.0, 0.0),
+            ] {
+                assert_near(field.sample(p), v);
+            }
+        }
+    }
+
+    #[test]
+    fn linear_fields_are_interpolated_exactly() {
+        let f = |p: Vec2| vec2(p.x, 2.0 * p.y - p.x);
+        let bilinear = field(f, Interpolation::Bilinear);
+        // Catmull-Rom splines only reproduce lines away from the edges, where they need
+        // nodes beyond the last
+        let bicubic = field(f, Interpolation::Bicubic);
+        for p in [
+            vec2(3.7, 12.1),
+            vec2(-25.5, 31.0),
+            vec2(0.0, 0.0),
+            vec2(12.0, -38.9),
+        ] {
+            assert_near(bilinear.sample(p), f(p));
+            assert_near(bicubic.sample(p), f(p));
+        }
+        assert_near(bilinear.sample(vec2(-48.0, 47.0)), f(vec2(-48.0, 47.0)));
+    }
+
+    #[test]
+    fn nearest_takes_the_closest_node() {
+        let field = field(|p| p, Interpolation::Nearest);
+        assert_near(field.sample(vec2(3.0, 16.0)), vec2(0.0, 20.0));
+        assert_near(field.sample(vec2(-200.0, 0.0)), vec2(-50.0, 0.0));
+    }
+
+    #[test]
+    fn traces_follow_the_field_to_its_edge() {
+        for integrator in [Integrator::Euler, Integrator::Rk2, Integrator::Rk4] {
+            let field = field(|_| vec2(0.0, 5.0), Interpolation::Bilinear);
+            let line = field.trace(vec2(10.0, 0.0), 1000, 2.0, integrator);
+            assert_eq!(line.len(), 26);
+            for (i, p) in line.iter().enumerate() {
+                assert_near(*p, vec2(10.0, i as f32 * 2.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! A uniform grid for finding which items are near a point, e.g. to check new shapes for
+//! collisions without comparing them with every shape placed so far.
+
+use nannou::prelude::*;
+
+/// Buckets item ids by the cells their bounding boxes cover.
+///
+/// Items outside `bounds` are kept in the cells along its border, so queries stay correct
+/// everywhere, just slower far outside.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    bounds: Rect,
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(bounds: Rect, cell_size: f32) -> Self {
+        let cols = (bounds.w() / cell_size).ceil().max(1.0) as usize;
+        let rows = (bounds.h() / cell_size).ceil().max(1.0) as usize;
+        Grid {
+            bounds,
+            cell_size,
+            cols,
+            rows,
+            cells: vec![vec![]; cols * rows],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(Vec::clear);
+    }
+
+    pub fn insert(&mut self, id: usize, bbox: Rect) {
+        for cell in self.cell_indices(bbox) {
+            self.cells[cell].push(id);
+        }
+    }
+
+    /// Ids of all items whose bounding boxes may overlap `bbox`. Items covering several cells
+    /// are returned once per cell.
+    pub fn query(&self, bbox: Rect) -> impl Iterator<Item = usize> + '_ {
+        self.cell_indices(bbox)
+            .flat_map(move |cell| self.cells[cell].iter().copied())
+    }
+
+    fn cell_indices(&self, bbox: Rect) -> impl Iterator<Item = usize> {
+        let col = |x: f32| {
+            let c = ((x - self.bounds.left()) / self.cell_size).floor();
+            c.clamp(0.0, (self.cols - 1) as f32) as usize
+        };
+        let row = |y: f32| {
+            let r = ((y - self.bounds.bottom()) / self.cell_size).floor();
+            r.clamp(0.0, (self.rows - 1) as f32) as usize
+        };
+        let (c0, c1) = (col(bbox.left()), col(bbox.right()));
+        let (r0, r1) = (row(bbox.bottom()), row(bbox.top()));
+        let cols = self.cols;
+        (r0..=r1).flat_map(move |r| (c0..=c1).map(move |c| r * cols + c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Rect {
+        Rect::from_xy_wh(vec2(x, y), Vec2::ZERO)
+    }
+
+    fn query(grid: &Grid, bbox: Rect) -> Vec<usize> {
+        let mut ids: Vec<_> = grid.query(bbox).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    #[test]
+    fn finds_nearby_items_only() {
+        let mut grid = Grid::new(Rect::from_w_h(100.0, 100.0), 10.0);
+        grid.insert(0, point(-45.0, -45.0));
+        grid.insert(1, point(0.0, 0.0));
+        grid.insert(2, point(45.0, 45.0));
+        assert_eq!(query(&grid, Rect::from_w_h(4.0, 4.0)), [1]);
+        assert_eq!(query(&grid, point(44.0, 44.0)), [2]);
+        assert_eq!(query(&grid, Rect::from_w_h(100.0, 100.0)), [0, 1, 2]);
+    }
+
+    #[test]
+    fn items_spanning_cells_are_found_from_each() {
+        let mut grid = Grid::new(Rect::from_w_h(100.0, 100.0), 10.0);
+        grid.insert(7, Rect::from_w_h(30.0, 4.0));
+        assert_eq!(query(&grid, point(-14.0, 0.0)), [7]);
+        assert_eq!(query(&grid, point(14.0, 0.0)), [7]);
+        assert!(query(&grid, point(0.0, 20.0)).is_empty());
+    }
+
+    #[test]
+    fn items_outside_the_bounds_are_kept_at_the_border() {
+        let mut grid = Grid::new(Rect::from_w_h(100.0, 100.0), 10.0);
+        grid.insert(0, point(500.0, 0.0));
+        assert_eq!(query(&grid, point(400.0, 0.0)), [0]);
+        assert_eq!(query(&grid, point(49.0, 0.0)), [0]);
+        assert!(query(&grid, point(-49.0, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn clear_removes_everything() {
+        let mut grid = Grid::new(Rect::from_w_h(100.0, 100.0), 10.0);
+        grid.insert(0, point(0.0, 0.0));
+        grid.clear();
+        assert!(query(&grid, Rect::from_w_h(100.0, 100.0)).is_empty());
+    }
+}
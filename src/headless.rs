@@ -0,0 +1,95 @@
+//! Running sketches without a window or a GPU, e.g. to regenerate the screenshots in `assets/`
+//! on a build machine.
+
+use std::error::Error;
+
+use nannou::prelude::*;
+
+use crate::{
+    clock::{self, Clock, Timing},
+    raster::Raster,
+    record::Recorder,
+    sketch::{self, Args, Mode, Sketch},
+    svg::Svg,
+    Canvas, FrameInfo,
+};
+
+/// Run the sketch for `args.frames` frames into an image the size of its window, then save it.
+/// With `--record`, every frame is saved and assembled into an animation instead.
+pub fn run<S: Sketch>(args: &Args) -> Result<(), Box<dyn Error>> {
+    let [width, height] = S::SIZE;
+    let mut sketch: S = sketch::new_sketch(args);
+    let record_frames = args.record_frames::<S>();
+    let frames = record_frames.unwrap_or(args.frames);
+    let ext = match record_frames {
+        Some(_) => args.format.extension(),
+        None => "png",
+    };
+    let out = args
+        .out
+        .clone()
+        .unwrap_or_else(|| sketch::capture_path(&sketch, ext));
+    let mut recorder = match record_frames {
+        Some(_) => Some(Recorder::new(&out)?),
+        None => None,
+    };
+    let scale = args.print_scale::<S>();
+    let mut raster = Raster::scaled(width, height, scale)?;
+    let info = simulate(&mut sketch, frames, &mut raster, recorder.as_mut())?;
+
+    match recorder {
+        Some(recorder) => {
+            recorder.finish(args.format, clock::FPS)?;
+        }
+        None if args.paper.is_some() => raster.save_png_at_dpi(&out, args.dpi)?,
+        None => raster.save_png(&out)?,
+    }
+    sketch::save_sidecar(&sketch, &out, &info, scale)?;
+    println!("Saved {}", out.display());
+
+    if let Some(path) = &args.svg {
+        let canvas = Canvas::new();
+        canvas.background(S::BACKGROUND);
+        sketch.draw_vector(&canvas, &info);
+        let mut svg = Svg::new(width, height);
+        svg.render(&canvas);
+        svg.save(path, args.dpi)?;
+        println!("Saved {}", path.display());
+    }
+    Ok(())
+}
+
+/// Run a freshly (re)started sketch for `frames` frames with a fixed timestep, drawing into
+/// `raster`, and into `recorder` if given. Returns the last frame's info.
+pub fn simulate<S: Sketch>(
+    sketch: &mut S,
+    frames: u64,
+    raster: &mut Raster,
+    mut recorder: Option<&mut Recorder>,
+) -> Result<FrameInfo, Box<dyn Error>> {
+    let [width, height] = S::SIZE;
+    let canvas = Canvas::new();
+    let rect = Rect::from_w_h(width as f32, height as f32);
+    let mut clock = Clock::new(Timing::Fixed);
+    let mut info = clock.info(rect, raster.scale());
+    for _ in 0..frames {
+        clock.step();
+        info = clock.info(rect, raster.scale());
+        sketch.update(&info);
+        // Frames that get drawn over anyway can be skipped, which matters for big rasters.
+        let visible = S::MODE == Mode::Accumulate || recorder.is_some() || info.frame == frames;
+        if !visible {
+            continue;
+        }
+        if S::MODE == Mode::Clear || info.frame == 1 {
+            canvas.background(S::BACKGROUND);
+        }
+        sketch.draw(&canvas, &info);
+        raster.render(&canvas);
+        canvas.reset();
+        if let Some(recorder) = &mut recorder {
+            recorder.add(raster)?;
+        }
+    }
+    Ok(info)
+}
@@ -0,0 +1,82 @@
+//! Images stretched over an area of the canvas, for sketches that take their shapes or colours
+//! from a picture.
+
+use std::{error::Error, path::Path};
+
+use nannou::{image, prelude::*};
+
+use crate::raster::Raster;
+
+/// An RGBA image covering `rect`, which can be sampled at any point of the canvas.
+#[derive(Clone, Debug)]
+pub struct ImageMap {
+    width: u32,
+    height: u32,
+    /// sRGB values in 0..1, row by row from the top.
+    pixels: Vec<Vec4>,
+    rect: Rect,
+}
+
+impl ImageMap {
+    pub fn new(width: u32, height: u32, rgba: &[u8], rect: Rect) -> Self {
+        let pixels = rgba
+            .chunks_exact(4)
+            .map(|p| vec4(p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32) / 255.0)
+            .collect();
+        ImageMap {
+            width,
+            height,
+            pixels,
+            rect,
+        }
+    }
+
+    /// Load an image file and stretch it over `rect`.
+    pub fn open<P: AsRef<Path>>(path: P, rect: Rect) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let img = image::open(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .to_rgba8();
+        Ok(ImageMap::new(img.width(), img.height(), img.as_raw(), rect))
+    }
+
+    /// Use a rendered drawing as an image, e.g. to take shapes from text.
+    pub fn from_raster(raster: &Raster, rect: Rect) -> Self {
+        ImageMap::new(raster.width(), raster.height(), &raster.rgba(), rect)
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Size of a pixel in the units of `rect`.
+    pub fn pixel_size(&self) -> Vec2 {
+        self.rect.wh() / vec2(self.width as f32, self.height as f32)
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Vec4 {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.pixels[y * self.width as usize + x]
+    }
+
+    /// The colour at `p`, interpolated between pixels. Points outside the image take the
+    /// colour of the nearest edge.
+    pub fn sample(&self, p: Vec2) -> Srgba {
+        let x = (p.x - self.rect.left()) / self.rect.w() * self.width as f32 - 0.5;
+        let y = (self.rect.top() - p.y) / self.rect.h() * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self.pixel(x0, y0).lerp(self.pixel(x0 + 1, y0), fx);
+        let bottom = self.pixel(x0, y0 + 1).lerp(self.pixel(x0 + 1, y0 + 1), fx);
+        let c = top.lerp(bottom, fy);
+        srgba(c.x, c.y, c.z, c.w)
+    }
+
+    /// Brightness at `p` in 0..1.
+    pub fn luma(&self, p: Vec2) -> f32 {
+        let c = self.sample(p);
+        0.2126 * c.red + 0.7152 * c.green + 0.0722 * c.blue
+    }
+}
@@ -0,0 +1,45 @@
+//! Shared bits for the sketches in `src/bin/`.
+
+use nannou::prelude::*;
+
+pub mod canvas;
+pub mod clock;
+pub mod field;
+pub mod flow;
+pub mod grid;
+pub mod headless;
+pub mod image_map;
+pub mod overlay;
+pub mod palette;
+pub mod preset;
+pub mod print;
+pub mod raster;
+pub mod record;
+pub mod sketch;
+pub mod streamlines;
+pub mod svg;
+
+pub use canvas::Canvas;
+pub use sketch::{run, Mode, Sketch};
+
+/// Everything a sketch needs to know about the frame it's drawing, whether it's being drawn
+/// in a window or offline.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameInfo {
+    /// The drawable area, centered on the origin like `App::window_rect`.
+    pub rect: Rect,
+    /// Number of frames since the sketch last (re)started, starting at 1.
+    pub frame: u64,
+    /// Time in seconds since the sketch started.
+    pub time: f32,
+    /// Pixels per unit of `rect`, e.g. more than 1 for print renders or high DPI screens.
+    pub scale: f32,
+}
+
+/// Name of the running binary, used to name output files.
+pub fn exe_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "sketch".to_string())
+}
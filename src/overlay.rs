@@ -0,0 +1,150 @@
+//! Debug views of vector fields, shown over flow field sketches to see what their lines follow.
+
+use nannou::{
+    color::IntoLinSrgba,
+    image::{Rgba, RgbaImage},
+    prelude::*,
+};
+use nannou_egui::egui;
+
+use crate::{
+    canvas::{self, Stroke},
+    Canvas,
+};
+
+/// Heatmaps are sampled this many times more densely than arrows along each side.
+const HEATMAP_DETAIL: f32 = 4.0;
+
+/// What the overlay shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldView {
+    #[default]
+    Off,
+    /// Arrows along the field, as long as it is strong and coloured by direction.
+    Arrows,
+    /// The direction of the field as a hue.
+    Angle,
+    /// The strength of the field, from blue where it is weakest to red where it is strongest.
+    Magnitude,
+}
+
+impl FieldView {
+    pub const ALL: [FieldView; 4] = [
+        FieldView::Off,
+        FieldView::Arrows,
+        FieldView::Angle,
+        FieldView::Magnitude,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FieldView::Off => "Off",
+            FieldView::Arrows => "Arrows",
+            FieldView::Angle => "Angle",
+            FieldView::Magnitude => "Magnitude",
+        }
+    }
+}
+
+/// A view of a vector field, for sketches to draw as their overlay.
+#[derive(Clone, Debug)]
+pub struct FieldOverlay {
+    pub view: FieldView,
+    /// Distance between arrows, in the units of the field.
+    pub spacing: f32,
+    pub opacity: f32,
+}
+
+impl Default for FieldOverlay {
+    fn default() -> Self {
+        FieldOverlay {
+            view: FieldView::Off,
+            spacing: 20.0,
+            opacity: 0.8,
+        }
+    }
+}
+
+impl FieldOverlay {
+    /// Switch to the next view, or off after the last one.
+    pub fn cycle(&mut self) {
+        let i = FieldView::ALL.iter().position(|&v| v == self.view).unwrap();
+        self.view = FieldView::ALL[(i + 1) % FieldView::ALL.len()];
+        println!("Field overlay: {}", self.view.name());
+    }
+
+    /// Draw the field `f` over `rect`, which is in the units of `canvas`.
+    pub fn draw(&self, canvas: &Canvas, rect: Rect, f: impl Fn(Vec2) -> Vec2) {
+        let spacing = match self.view {
+            FieldView::Off => return,
+            FieldView::Arrows => self.spacing,
+            FieldView::Angle | FieldView::Magnitude => self.spacing / HEATMAP_DETAIL,
+        };
+        let cols = (rect.w() / spacing).ceil().max(1.0) as usize;
+        let rows = (rect.h() / spacing).ceil().max(1.0) as usize;
+        let cell = rect.wh() / vec2(cols as f32, rows as f32);
+        // Cell centres, row by row from the top of `rect` like the pixels of an image
+        let centre = |i: usize| {
+            let (col, row) = ((i % cols) as f32, (i / cols) as f32);
+            vec2(
+                rect.left() + (col + 0.5) * cell.x,
+                rect.top() - (row + 0.5) * cell.y,
+            )
+        };
+        let vectors: Vec<Vec2> = (0..cols * rows).map(|i| f(centre(i))).collect();
+        let max = vectors.iter().map(|v| v.length()).fold(0.0, f32::max);
+        if max <= 0.0 {
+            return;
+        }
+
+        if self.view == FieldView::Arrows {
+            for (i, v) in vectors.iter().enumerate() {
+                let stroke = Stroke::new(1.0, self.angle_color(*v)).caps_round();
+                arrow(canvas, centre(i), *v / max * spacing * 0.8, stroke);
+            }
+            return;
+        }
+        let image = RgbaImage::from_fn(cols as u32, rows as u32, |x, y| {
+            let v = vectors[y as usize * cols + x as usize];
+            let color = match self.view {
+                FieldView::Angle => self.angle_color(v),
+                _ => hsva(0.66 * (1.0 - v.length() / max), 0.8, 0.9, self.opacity),
+            };
+            let c = canvas::to_srgba(color.into_lin_srgba());
+            let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+            Rgba([byte(c.red), byte(c.green), byte(c.blue), byte(c.alpha)])
+        });
+        canvas.image(rect, image);
+    }
+
+    fn angle_color(&self, v: Vec2) -> Hsva {
+        if v == Vec2::ZERO {
+            return hsva(0.0, 0.0, 0.5, self.opacity);
+        }
+        let hue = (v.y.atan2(v.x) / TAU).rem_euclid(1.0);
+        hsva(hue, 0.8, 0.8, self.opacity)
+    }
+
+    /// Controls for the overlay, to put in a sketch's settings window.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for view in FieldView::ALL {
+                ui.selectable_value(&mut self.view, view, view.name());
+            }
+        });
+        let slider = egui::Slider::new(&mut self.spacing, 5.0..=100.0).logarithmic(true);
+        ui.add(slider.text("Spacing"));
+        ui.add(egui::Slider::new(&mut self.opacity, 0.05..=1.0).text("Opacity"));
+    }
+}
+
+/// An arrow from `from` along `v`, with a head a third of its length.
+fn arrow(canvas: &Canvas, from: Vec2, v: Vec2, stroke: Stroke) {
+    let tip = from + v;
+    let back = -v / 3.0;
+    canvas.polyline([from, tip], stroke);
+    canvas.polyline(
+        [tip + back.rotate(0.5), tip, tip + back.rotate(-0.5)],
+        stroke,
+    );
+}
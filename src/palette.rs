@@ -0,0 +1,454 @@
+//! Named colour palettes, built in or loaded from `palettes/`.
+//!
+//! Palette files can be GIMP palettes (`.gpl`), Adobe swatch exchange files (`.ase`), or text
+//! files (`.hex`, `.txt`) with one hex colour per line, optionally followed by a weight, or
+//! with coolors.co URLs like `https://coolors.co/264653-2a9d8f-e9c46a`. Text after a `;` is
+//! ignored.
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use nannou::{prelude::*, rand::Rng};
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    name: String,
+    colors: Vec<Rgb>,
+    /// Relative chance of picking each colour, or `None` for all alike.
+    weights: Option<Vec<f32>>,
+}
+
+impl Palette {
+    /// A palette of at least one colour.
+    pub fn new<S: Into<String>>(name: S, colors: Vec<Rgb>) -> Result<Self, Box<dyn Error>> {
+        Ok(Palette {
+            name: name.into(),
+            colors: non_empty(colors)?,
+            weights: None,
+        })
+    }
+
+    /// Pick colours with the given relative frequencies, one per colour. Weights can't be
+    /// negative, and at least one has to be above 0.
+    pub fn with_weights(mut self, weights: Vec<f32>) -> Result<Self, Box<dyn Error>> {
+        if weights.len() != self.colors.len() {
+            let (w, c) = (weights.len(), self.colors.len());
+            return Err(format!("{} weights for {} colours", w, c).into());
+        }
+        if let Some(w) = weights.iter().find(|w| !(w.is_finite() && **w >= 0.0)) {
+            return Err(format!("bad weight {}", w).into());
+        }
+        if weights.iter().sum::<f32>() <= 0.0 {
+            return Err("all weights are 0".into());
+        }
+        self.weights = Some(weights);
+        Ok(self)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn colors(&self) -> &[Rgb] {
+        &self.colors
+    }
+
+    /// A random colour, according to the weights if there are any.
+    pub fn choose(&self, rng: &mut impl Rng) -> Rgb {
+        let Some(weights) = &self.weights else {
+            return self.colors[rng.gen_range(0..self.colors.len())];
+        };
+        let mut x = rng.gen_range(0.0..weights.iter().sum::<f32>());
+        for (color, &weight) in self.colors.iter().zip(weights) {
+            if x < weight {
+                return *color;
+            }
+            x -= weight;
+        }
+        // Only reachable through rounding errors
+        *self.colors.last().unwrap()
+    }
+
+    /// The colour `t` of the way through the palette, blending between neighbouring colours.
+    pub fn gradient(&self, t: f32) -> Rgb {
+        let colors = &self.colors;
+        let x = t.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+        let i = (x.floor() as usize).min(colors.len().saturating_sub(2));
+        let (a, b) = (colors[i], colors[(i + 1).min(colors.len() - 1)]);
+        let f = x - i as f32;
+        rgb(
+            a.red + (b.red - a.red) * f,
+            a.green + (b.green - a.green) * f,
+            a.blue + (b.blue - a.blue) * f,
+        )
+    }
+
+    /// The palettes that are always available.
+    pub fn builtin() -> Vec<Palette> {
+        let tango = |rgbs: &[(f32, f32, f32)]| rgbs.iter().map(|&(r, g, b)| rgb(r, g, b)).collect();
+        // Enough steps round the colour wheel that blending between them looks like turning the
+        // hue
+        let hues = (0..=36)
+            .map(|i| hsv(i as f32 / 36.0, 0.8, 0.8).into())
+            .collect();
+        vec![
+            Palette::new("ink", vec![rgb(0.0, 0.0, 0.0)]).unwrap(),
+            Palette::new("hues", hues).unwrap(),
+            Palette::new(
+                "tango",
+                tango(&[
+                    (0.988, 0.909, 0.310),
+                    (0.930, 0.837, 0.000),
+                    (0.768, 0.627, 0.000),
+                    (0.545, 0.886, 0.204),
+                    (0.455, 0.824, 0.086),
+                    (0.304, 0.604, 0.024),
+                    (0.988, 0.690, 0.244),
+                    (0.960, 0.480, 0.000),
+                    (0.808, 0.364, 0.000),
+                    (0.446, 0.623, 0.812),
+                    (0.204, 0.395, 0.644),
+                    (0.125, 0.287, 0.529),
+                    (0.678, 0.498, 0.657),
+                    (0.460, 0.314, 0.482),
+                    (0.362, 0.208, 0.400),
+                    (0.914, 0.729, 0.432),
+                    (0.757, 0.493, 0.067),
+                    (0.560, 0.348, 0.008),
+                    (0.937, 0.161, 0.161),
+                    (0.800, 0.000, 0.000),
+                    (0.644, 0.000, 0.000),
+                ]),
+            )
+            .unwrap(),
+            Palette::new(
+                "tango-grays",
+                tango(&[
+                    (0.933, 0.933, 0.925),
+                    (0.827, 0.843, 0.811),
+                    (0.729, 0.741, 0.713),
+                    (0.533, 0.541, 0.521),
+                    (0.333, 0.341, 0.325),
+                    (0.180, 0.204, 0.212),
+                ]),
+            )
+            .unwrap(),
+        ]
+    }
+
+    /// A built in palette, or one from `palettes/` by file stem.
+    pub fn named(name: &str) -> Result<Palette, Box<dyn Error>> {
+        list()
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("no palette named {:?} in {}", name, dir().display()).into())
+    }
+
+    /// Load a palette file, named after its file stem.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Palette, Box<dyn Error>> {
+        let path = path.as_ref();
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+        match ext.as_ref() {
+            "gpl" => Palette::new(name, parse_gpl(&fs::read_to_string(path)?)?),
+            "ase" => Palette::new(name, parse_ase(&fs::read(path)?)?),
+            _ => {
+                let (colors, weights) = parse_hex_list(&fs::read_to_string(path)?)?;
+                let palette = Palette::new(name, colors)?;
+                if weights.iter().all(|&w| w == 1.0) {
+                    Ok(palette)
+                } else {
+                    palette.with_weights(weights)
+                }
+            }
+        }
+    }
+}
+
+pub fn dir() -> PathBuf {
+    PathBuf::from("palettes")
+}
+
+/// The built in palettes followed by those in `palettes/`, in alphabetical order. Files that
+/// fail to load are reported and skipped.
+pub fn list() -> Vec<Palette> {
+    let mut paths: Vec<_> = fs::read_dir(dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ["gpl", "ase", "hex", "txt"].iter().any(|e| ext == *e))
+        })
+        .collect();
+    paths.sort();
+    let mut palettes = Palette::builtin();
+    for path in paths {
+        match Palette::load(&path) {
+            Ok(palette) => palettes.push(palette),
+            Err(e) => println!("Couldn't load {}: {}", path.display(), e),
+        }
+    }
+    palettes
+}
+
+fn parse_gpl(text: &str) -> Result<Vec<Rgb>, Box<dyn Error>> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err("missing GIMP Palette header".into());
+    }
+    let mut colors = vec![];
+    for line in lines {
+        // Skip comments and `Name:` or `Columns:` headers; colours may be followed by a name
+        let tokens = line.split_whitespace();
+        if tokens
+            .clone()
+            .next()
+            .is_none_or(|t| t.parse::<u8>().is_err())
+        {
+            continue;
+        }
+        let channels: Vec<u8> = tokens.take(3).map(str::parse).collect::<Result<_, _>>()?;
+        if let [r, g, b] = channels[..] {
+            colors.push(Srgb::new(r, g, b).into_format());
+        }
+    }
+    non_empty(colors)
+}
+
+/// Colours and weights from hex codes and coolors URLs.
+fn parse_hex_list(text: &str) -> Result<(Vec<Rgb>, Vec<f32>), Box<dyn Error>> {
+    let mut colors = vec![];
+    let mut weights = vec![];
+    for line in text.lines() {
+        let line = line.split(';').next().unwrap().trim();
+        if let Some((_, codes)) = line.split_once("coolors.co/") {
+            let codes = codes.rsplit('/').next().unwrap();
+            for code in codes.split('-') {
+                colors.push(parse_hex(code)?);
+                weights.push(1.0);
+            }
+        } else if let Some(code) = line.split_whitespace().next() {
+            colors.push(parse_hex(code)?);
+            weights.push(match line.split_whitespace().nth(1) {
+                Some(weight) => weight.parse()?,
+                None => 1.0,
+            });
+        }
+    }
+    Ok((non_empty(colors)?, weights))
+}
+
+/// `#rrggbb`, `rrggbb` or `#rgb`.
+fn parse_hex(code: &str) -> Result<Rgb, Box<dyn Error>> {
+    let hex = code.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).map_err(|_| format!("bad colour {:?}", code))?;
+    let (r, g, b) = match hex.len() {
+        6 => (value >> 16, value >> 8 & 0xff, value & 0xff),
+        3 => (
+            (value >> 8) * 0x11,
+            (value >> 4 & 0xf) * 0x11,
+            (value & 0xf) * 0x11,
+        ),
+        _ => return Err(format!("bad colour {:?}", code).into()),
+    };
+    Ok(Srgb::new(r as u8, g as u8, b as u8).into_format())
+}
+
+/// Adobe swatch exchange: big endian blocks of colour entries, possibly in groups. RGB, gray
+/// and CMYK entries are supported.
+fn parse_ase(data: &[u8]) -> Result<Vec<Rgb>, Box<dyn Error>> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.bytes(4)? != b"ASEF" {
+        return Err("not an ASE file".into());
+    }
+    reader.bytes(4)?; // version
+    let blocks = reader.u32()?;
+    let mut colors = vec![];
+    for _ in 0..blocks {
+        let kind = reader.u16()?;
+        let len = reader.u32()? as usize;
+        let block = reader.bytes(len)?;
+        if kind != 0x0001 {
+            // Group start or end
+            continue;
+        }
+        let mut entry = Reader {
+            data: block,
+            pos: 0,
+        };
+        let name_len = entry.u16()? as usize;
+        entry.bytes(name_len * 2)?;
+        let model = entry.bytes(4)?;
+        let color = match model {
+            b"RGB " => rgb(entry.f32()?, entry.f32()?, entry.f32()?),
+            b"Gray" => {
+                let v = entry.f32()?;
+                rgb(v, v, v)
+            }
+            b"CMYK" => {
+                let (c, m, y, k) = (entry.f32()?, entry.f32()?, entry.f32()?, entry.f32()?);
+                rgb(
+                    (1.0 - c) * (1.0 - k),
+                    (1.0 - m) * (1.0 - k),
+                    (1.0 - y) * (1.0 - k),
+                )
+            }
+            _ => {
+                let model = String::from_utf8_lossy(model);
+                return Err(format!("unsupported colour model {:?}", model).into());
+            }
+        };
+        colors.push(color);
+    }
+    non_empty(colors)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("unexpected end of file")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, Box<dyn Error>> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, Box<dyn Error>> {
+        Ok(f32::from_be_bytes(self.bytes(4)?.try_into()?))
+    }
+}
+
+fn non_empty(colors: Vec<Rgb>) -> Result<Vec<Rgb>, Box<dyn Error>> {
+    if colors.is_empty() {
+        Err("no colours found".into())
+    } else {
+        Ok(colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn bytes(c: Rgb) -> (u8, u8, u8) {
+        let c: Srgb<u8> = c.into_format();
+        (c.red, c.green, c.blue)
+    }
+
+    #[test]
+    fn hex_codes() {
+        assert_eq!(bytes(parse_hex("#264653").unwrap()), (0x26, 0x46, 0x53));
+        assert_eq!(bytes(parse_hex("2a9d8f").unwrap()), (0x2a, 0x9d, 0x8f));
+        assert_eq!(bytes(parse_hex("#fa0").unwrap()), (0xff, 0xaa, 0x00));
+        assert!(parse_hex("#12345").is_err());
+        assert!(parse_hex("#gggggg").is_err());
+    }
+
+    #[test]
+    fn hex_lists_with_weights_and_urls() {
+        let text = "; sunset\n#ff0000 3\n#00ff00 ; green\n\nhttps://coolors.co/0000ff-ffffff\n";
+        let (colors, weights) = parse_hex_list(text).unwrap();
+        let colors: Vec<_> = colors.into_iter().map(bytes).collect();
+        assert_eq!(
+            colors,
+            [(255, 0, 0), (0, 255, 0), (0, 0, 255), (255, 255, 255)]
+        );
+        assert_eq!(weights, [3.0, 1.0, 1.0, 1.0]);
+        assert!(parse_hex_list("#ff0000 heavy").is_err());
+        assert!(parse_hex_list("; nothing here").is_err());
+    }
+
+    #[test]
+    fn gimp_palettes() {
+        let text = "GIMP Palette\nName: test\nColumns: 2\n#\n255 0 0 Red\n  0 128 255\n";
+        let colors: Vec<_> = parse_gpl(text).unwrap().into_iter().map(bytes).collect();
+        assert_eq!(colors, [(255, 0, 0), (0, 128, 255)]);
+        assert!(parse_gpl("255 0 0\n").is_err());
+        assert!(parse_gpl("GIMP Palette\nName: empty\n").is_err());
+    }
+
+    /// An ASE colour entry block named "c".
+    fn ase_entry(model: &[u8; 4], values: &[f32]) -> Vec<u8> {
+        let mut entry = vec![0, 2, 0, b'c', 0, 0];
+        entry.extend_from_slice(model);
+        for v in values {
+            entry.extend_from_slice(&v.to_be_bytes());
+        }
+        entry.extend_from_slice(&[0, 2]); // colour type
+        let mut block = vec![0, 1];
+        block.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+        block.extend(entry);
+        block
+    }
+
+    #[test]
+    fn ase_files() {
+        let mut data = b"ASEF\x00\x01\x00\x00".to_vec();
+        data.extend_from_slice(&4u32.to_be_bytes());
+        // Group start and end around the colours
+        data.extend_from_slice(&[0xc0, 0x01, 0, 0, 0, 0]);
+        data.extend(ase_entry(b"RGB ", &[1.0, 0.0, 0.0]));
+        data.extend(ase_entry(b"Gray", &[1.0]));
+        data.extend_from_slice(&[0xc0, 0x02, 0, 0, 0, 0]);
+        let colors: Vec<_> = parse_ase(&data).unwrap().into_iter().map(bytes).collect();
+        assert_eq!(colors, [(255, 0, 0), (255, 255, 255)]);
+        assert!(parse_ase(&data[..data.len() - 10]).is_err());
+        assert!(parse_ase(b"RIFF").is_err());
+    }
+
+    #[test]
+    fn bad_weights_are_errors() {
+        let palette = || Palette::new("test", vec![rgb(1.0, 0.0, 0.0), rgb(0.0, 1.0, 0.0)]);
+        assert!(palette().unwrap().with_weights(vec![1.0]).is_err());
+        assert!(palette().unwrap().with_weights(vec![1.0, -1.0]).is_err());
+        assert!(palette()
+            .unwrap()
+            .with_weights(vec![1.0, f32::NAN])
+            .is_err());
+        assert!(palette().unwrap().with_weights(vec![0.0, 0.0]).is_err());
+        assert!(palette().unwrap().with_weights(vec![0.0, 2.0]).is_ok());
+        assert!(Palette::new("empty", vec![]).is_err());
+    }
+
+    #[test]
+    fn zero_weights_are_never_chosen() {
+        let green = rgb(0.0, 1.0, 0.0);
+        let palette = Palette::new("test", vec![rgb(1.0, 0.0, 0.0), green])
+            .unwrap()
+            .with_weights(vec![0.0, 1.0])
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!((0..100).all(|_| palette.choose(&mut rng) == green));
+    }
+
+    #[test]
+    fn hues_follow_the_colour_wheel() {
+        let hues = Palette::named("hues").unwrap();
+        for t in [0.0, 0.1, 0.3, 0.66, 0.9] {
+            let expected: Rgb = hsv(t, 0.8, 0.8).into();
+            let c = hues.gradient(t);
+            let d =
+                vec3(c.red, c.green, c.blue) - vec3(expected.red, expected.green, expected.blue);
+            assert!(d.abs().max_element() < 0.01, "{} gives {:?}", t, c);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Saving and loading a sketch's seed and parameters, so good configurations can be shared.
+//!
+//! Presets are TOML files by default, but anything ending in `.json` is read and written as
+//! JSON instead. Captures are saved with a preset next to them, which also records how the
+//! capture was made.
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{exe_name, FrameInfo, Sketch};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Preset<P> {
+    pub seed: u64,
+    pub params: P,
+    /// Only present in presets saved next to a capture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<Capture>,
+}
+
+/// Where a captured image came from, so it can be traced back to its exact configuration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Capture {
+    pub bin: String,
+    pub frame: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Pixels per unit of `width` and `height`.
+    pub scale: f32,
+    /// `git describe` of the working tree, if it could be determined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl Capture {
+    pub fn new(info: &FrameInfo, scale: f32) -> Self {
+        Capture {
+            bin: exe_name(),
+            frame: info.frame,
+            width: info.rect.w() as u32,
+            height: info.rect.h() as u32,
+            scale,
+            commit: git_commit(),
+        }
+    }
+}
+
+impl<P: Serialize + DeserializeOwned> Preset<P> {
+    /// The current configuration of `sketch`.
+    pub fn of<S: Sketch<Params = P>>(sketch: &S) -> Self {
+        Preset {
+            seed: sketch.seed(),
+            params: sketch.params(),
+            capture: None,
+        }
+    }
+
+    /// Put `sketch` in this configuration. The caller should restart it afterwards.
+    pub fn apply<S: Sketch<Params = P>>(self, sketch: &mut S) {
+        sketch.set_seed(self.seed);
+        sketch.set_params(self.params);
+    }
+
+    pub fn load<Q: AsRef<Path>>(path: Q) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        if is_json(path) {
+            Ok(serde_json::from_str(&text)?)
+        } else {
+            Ok(toml::from_str(&text)?)
+        }
+    }
+
+    pub fn save<Q: AsRef<Path>>(&self, path: Q) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = if is_json(path) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            toml::to_string_pretty(self)?
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// Where the presets of the running sketch are kept: `presets/<bin>/`.
+pub fn dir() -> PathBuf {
+    Path::new("presets").join(exe_name())
+}
+
+/// All presets of the running sketch, in alphabetical order.
+pub fn list() -> Vec<PathBuf> {
+    let mut presets: Vec<_> = fs::read_dir(dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "toml" || ext == "json")
+        })
+        .collect();
+    presets.sort();
+    presets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Params {
+        count: u32,
+        scale: f32,
+        palette: String,
+    }
+
+    fn preset() -> Preset<Params> {
+        Preset {
+            seed: 123456,
+            params: Params {
+                count: 42,
+                scale: 0.25,
+                palette: "ink".to_string(),
+            },
+            capture: None,
+        }
+    }
+
+    #[test]
+    fn presets_round_trip_as_toml_and_json() {
+        let dir = std::env::temp_dir().join(format!("presets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["preset.toml", "preset.json"] {
+            let path = dir.join(name);
+            preset().save(&path).unwrap();
+            let loaded = Preset::<Params>::load(&path).unwrap();
+            assert_eq!(loaded.seed, 123456);
+            assert_eq!(loaded.params, preset().params);
+            assert!(loaded.capture.is_none());
+        }
+        let json = fs::read_to_string(dir.join("preset.json")).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn captures_are_kept() {
+        let text = "seed = 7\n\n[params]\ncount = 1\nscale = 2.0\npalette = \"hues\"\n\n\
+                    [capture]\nbin = \"circles\"\nframe = 60\nwidth = 800\nheight = 600\n\
+                    scale = 4.0\n";
+        let preset: Preset<Params> = toml::from_str(text).unwrap();
+        let capture = preset.capture.as_ref().unwrap();
+        assert_eq!((capture.frame, capture.width, capture.height), (60, 800, 600));
+        assert_eq!(capture.commit, None);
+        let again: Preset<Params> = toml::from_str(&toml::to_string(&preset).unwrap()).unwrap();
+        assert_eq!(again.capture.unwrap().scale, 4.0);
+    }
+
+    #[test]
+    fn missing_params_are_errors() {
+        assert!(toml::from_str::<Preset<Params>>("seed = 7\n").is_err());
+        assert!(serde_json::from_str::<Preset<Params>>("{\"seed\": 7, \"params\": {}}").is_err());
+    }
+}
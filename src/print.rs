@@ -0,0 +1,43 @@
+//! Sizing exports for physical prints.
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Paper {
+    A0,
+    A1,
+    A2,
+    A3,
+    A4,
+    A5,
+    Letter,
+}
+
+impl Paper {
+    /// Size in millimetres, portrait.
+    pub fn size_mm(self) -> (f32, f32) {
+        match self {
+            Paper::A0 => (841.0, 1189.0),
+            Paper::A1 => (594.0, 841.0),
+            Paper::A2 => (420.0, 594.0),
+            Paper::A3 => (297.0, 420.0),
+            Paper::A4 => (210.0, 297.0),
+            Paper::A5 => (148.0, 210.0),
+            Paper::Letter => (215.9, 279.4),
+        }
+    }
+
+    /// Pixels per canvas unit for a drawing of `size` to fill as much of the paper as possible
+    /// at `dpi`, turning the paper to match the drawing's orientation.
+    pub fn fit_scale(self, size: [u32; 2], dpi: f32) -> f32 {
+        let (short, long) = self.size_mm();
+        let [width, height] = size.map(|s| s as f32);
+        let (paper_w, paper_h) = if width > height {
+            (long, short)
+        } else {
+            (short, long)
+        };
+        let px = |mm: f32| mm / 25.4 * dpi;
+        (px(paper_w) / width).min(px(paper_h) / height)
+    }
+}
@@ -0,0 +1,298 @@
+//! CPU rasterizer for `Canvas` drawings, based on `tiny-skia`.
+
+use std::{error::Error, fs::File, io::BufWriter, path::Path};
+
+use nannou::{
+    color::LinSrgba,
+    prelude::*,
+    text::{self, rt},
+};
+use tiny_skia::{
+    ColorU8, FillRule, FilterQuality, LineCap, LineJoin, Paint, PathBuilder, Pixmap, PixmapPaint,
+    Transform,
+};
+
+use crate::canvas::{self, Canvas, Cap, Join, Shape, Stroke};
+
+/// An image buffer that `Canvas` drawings can be rendered into.
+///
+/// Like a window frame, the buffer is not cleared between renders, so sketches that rely on
+/// accumulating their drawing over several frames work as expected.
+pub struct Raster {
+    pixmap: Pixmap,
+    /// Pixels per canvas unit.
+    scale: f32,
+}
+
+/// Largest raster allowed, in pixels: 16384 x 16384, or a gigabyte of RGBA.
+pub const MAX_PIXELS: u64 = 1 << 28;
+
+impl Raster {
+    pub fn new(width: u32, height: u32) -> Result<Self, Box<dyn Error>> {
+        Raster::scaled(width, height, 1.0)
+    }
+
+    /// A raster for a `width` x `height` drawing, at `scale` pixels per unit. Everything is
+    /// scaled alike, including stroke weights and text, so the result looks like an enlarged
+    /// version of the window.
+    pub fn scaled(width: u32, height: u32, scale: f32) -> Result<Self, Box<dyn Error>> {
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(format!("scale must be positive, got {}", scale).into());
+        }
+        let w = (width as f64 * scale as f64).round();
+        let h = (height as f64 * scale as f64).round();
+        if w * h > MAX_PIXELS as f64 {
+            return Err(format!("{}x{} is larger than {} pixels", w, h, MAX_PIXELS).into());
+        }
+        let pixmap = Pixmap::new(w as u32, h as u32)
+            .ok_or_else(|| format!("can't make a {}x{} raster", w, h))?;
+        Ok(Raster { pixmap, scale })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixmap.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixmap.height()
+    }
+
+    /// Pixels per canvas unit.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Maps nannou's coordinates (origin in the middle, y pointing up) to pixels.
+    fn base_transform(&self) -> Affine2 {
+        Affine2::from_translation(vec2(self.width() as f32, self.height() as f32) / 2.0)
+            * Affine2::from_scale(vec2(self.scale, -self.scale))
+    }
+
+    /// The pixels as straight (not premultiplied) RGBA bytes, row by row from the top.
+    pub fn rgba(&self) -> Vec<u8> {
+        to_rgba(&self.pixmap)
+    }
+
+    pub fn render(&mut self, canvas: &Canvas) {
+        let base = self.base_transform();
+        for item in canvas.items().iter() {
+            let t = base * item.transform;
+            let ts = to_transform(t);
+            match &item.shape {
+                Shape::Background(color) => self.pixmap.fill(to_color(*color)),
+                Shape::Polyline { points, stroke } => {
+                    let mut pb = PathBuilder::new();
+                    for (i, p) in points.iter().enumerate() {
+                        if i == 0 {
+                            pb.move_to(p.x, p.y);
+                        } else {
+                            pb.line_to(p.x, p.y);
+                        }
+                    }
+                    if let Some(path) = pb.finish() {
+                        self.stroke_path(&path, stroke, ts);
+                    }
+                }
+                Shape::Polygon { points, color } => {
+                    let mut pb = PathBuilder::new();
+                    for (i, p) in points.iter().enumerate() {
+                        if i == 0 {
+                            pb.move_to(p.x, p.y);
+                        } else {
+                            pb.line_to(p.x, p.y);
+                        }
+                    }
+                    pb.close();
+                    if let Some(path) = pb.finish() {
+                        self.pixmap
+                            .fill_path(&path, &paint(*color), FillRule::Winding, ts, None);
+                    }
+                }
+                Shape::Ellipse {
+                    center,
+                    size,
+                    color,
+                } => {
+                    let rect = tiny_skia::Rect::from_xywh(
+                        center.x - size.x / 2.0,
+                        center.y - size.y / 2.0,
+                        size.x,
+                        size.y,
+                    );
+                    if let Some(path) = rect.and_then(PathBuilder::from_oval) {
+                        self.pixmap
+                            .fill_path(&path, &paint(*color), FillRule::Winding, ts, None);
+                    }
+                }
+                Shape::Rect {
+                    center,
+                    size,
+                    rotation,
+                    stroke,
+                } => {
+                    let corners = canvas::rect_corners(*center, *size, *rotation);
+                    let mut pb = PathBuilder::new();
+                    pb.move_to(corners[0].x, corners[0].y);
+                    for c in &corners[1..] {
+                        pb.line_to(c.x, c.y);
+                    }
+                    pb.close();
+                    if let Some(path) = pb.finish() {
+                        self.stroke_path(&path, stroke, ts);
+                    }
+                }
+                Shape::Text {
+                    text,
+                    position,
+                    size,
+                    color,
+                } => self.fill_text(text, t.transform_point2(*position), *size, *color),
+                Shape::Image { rect, image } => {
+                    let mut pixmap = Pixmap::new(image.width(), image.height()).unwrap();
+                    for (px, p) in pixmap.pixels_mut().iter_mut().zip(image.pixels()) {
+                        *px = ColorU8::from_rgba(p[0], p[1], p[2], p[3]).premultiply();
+                    }
+                    let paint = PixmapPaint {
+                        quality: FilterQuality::Bilinear,
+                        ..Default::default()
+                    };
+                    let ts = to_transform(t * canvas::image_transform(*rect, image));
+                    self.pixmap
+                        .draw_pixmap(0, 0, pixmap.as_ref(), &paint, ts, None);
+                }
+            }
+        }
+    }
+
+    fn stroke_path(&mut self, path: &tiny_skia::Path, stroke: &Stroke, ts: Transform) {
+        let sk_stroke = tiny_skia::Stroke {
+            width: stroke.weight,
+            line_cap: match stroke.cap {
+                Cap::Butt => LineCap::Butt,
+                Cap::Round => LineCap::Round,
+            },
+            line_join: match stroke.join {
+                Join::Miter => LineJoin::Miter,
+                Join::Round => LineJoin::Round,
+            },
+            ..Default::default()
+        };
+        self.pixmap
+            .stroke_path(path, &paint(stroke.color), &sk_stroke, ts, None);
+    }
+
+    /// Text is laid out in pixel space, so it follows the position of the transform but not
+    /// its rotation or scale, apart from the raster's own scale.
+    fn fill_text(&mut self, s: &str, position: Vec2, size: u32, color: LinSrgba) {
+        let font = text::font::default_notosans();
+        let scale = text::Scale::uniform(size as f32 * self.scale);
+        let v_metrics = font.v_metrics(scale);
+        let width = font
+            .layout(s, scale, rt::point(0.0, 0.0))
+            .last()
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+            .unwrap_or(0.0);
+        let origin = rt::point(
+            position.x - width / 2.0,
+            position.y + (v_metrics.ascent + v_metrics.descent) / 2.0,
+        );
+        for glyph in font.layout(s, scale, origin) {
+            let Some(bb) = glyph.pixel_bounding_box() else {
+                continue;
+            };
+            glyph.draw(|x, y, coverage| {
+                let mut c = color;
+                c.alpha *= coverage;
+                let px = tiny_skia::Rect::from_xywh(
+                    (bb.min.x + x as i32) as f32,
+                    (bb.min.y + y as i32) as f32,
+                    1.0,
+                    1.0,
+                );
+                if let Some(px) = px {
+                    self.pixmap
+                        .fill_rect(px, &paint(c), Transform::identity(), None);
+                }
+            });
+        }
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        self.pixmap.save_png(path)?;
+        Ok(())
+    }
+
+    /// Save as a PNG that records its print resolution, so it prints at the intended size.
+    pub fn save_png_at_dpi<P: AsRef<Path>>(&self, path: P, dpi: f32) -> Result<(), Box<dyn Error>> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width(), self.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let ppm = (dpi / 0.0254).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: ppm,
+            yppu: ppm,
+            unit: png::Unit::Meter,
+        }));
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&to_rgba(&self.pixmap))?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Straight (not premultiplied) RGBA bytes.
+pub(crate) fn to_rgba(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect()
+}
+
+fn to_transform(t: Affine2) -> Transform {
+    Transform::from_row(
+        t.x_axis.x,
+        t.x_axis.y,
+        t.y_axis.x,
+        t.y_axis.y,
+        t.translation.x,
+        t.translation.y,
+    )
+}
+
+fn to_color(color: LinSrgba) -> tiny_skia::Color {
+    let c = canvas::to_srgba(color);
+    tiny_skia::Color::from_rgba(
+        c.red.clamp(0.0, 1.0),
+        c.green.clamp(0.0, 1.0),
+        c.blue.clamp(0.0, 1.0),
+        c.alpha.clamp(0.0, 1.0),
+    )
+    .unwrap()
+}
+
+fn paint(color: LinSrgba) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(to_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rasters_must_be_drawable_and_fit_in_memory() {
+        let raster = Raster::scaled(100, 50, 2.5).unwrap();
+        assert_eq!((raster.width(), raster.height()), (250, 125));
+        for scale in [0.0, -1.0, f32::NAN, f32::INFINITY, 200.0] {
+            assert!(Raster::scaled(1200, 1200, scale).is_err(), "{}", scale);
+        }
+        assert!(Raster::new(0, 10).is_err());
+    }
+}
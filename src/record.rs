@@ -0,0 +1,151 @@
+//! Recording animations as a numbered frame sequence, assembled into a GIF or APNG at the end.
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use tiny_skia::Pixmap;
+
+use crate::raster::{to_rgba, Raster};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Animated GIF. Frame delays are in hundredths of a second, so the frame rate is rounded.
+    Gif,
+    /// Animated PNG, with exact frame delays and full colour.
+    Apng,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Gif => "gif",
+            Format::Apng => "apng",
+        }
+    }
+}
+
+/// Collects the frames of an animation into `<name>-frames/frame-00001.png` and so on.
+pub struct Recorder {
+    out: PathBuf,
+    dir: PathBuf,
+    frames: u32,
+}
+
+impl Recorder {
+    /// Start recording the animation that will end up at `out`.
+    pub fn new<P: AsRef<Path>>(out: P) -> Result<Self, Box<dyn Error>> {
+        let out = out.as_ref().to_path_buf();
+        let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+        let dir = out.with_file_name(format!("{}-frames", stem));
+        fs::create_dir_all(&dir)?;
+        Ok(Recorder {
+            out,
+            dir,
+            frames: 0,
+        })
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn add(&mut self, raster: &Raster) -> Result<(), Box<dyn Error>> {
+        self.frames += 1;
+        raster.save_png(self.frame_path(self.frames))
+    }
+
+    fn frame_path(&self, n: u32) -> PathBuf {
+        self.dir.join(format!("frame-{:05}.png", n))
+    }
+
+    /// Assemble the recorded frames into an animation playing at `fps`, looping forever. Fails
+    /// if no frames were recorded, or if they're too big for a GIF.
+    pub fn finish(self, format: Format, fps: f32) -> Result<PathBuf, Box<dyn Error>> {
+        if self.frames == 0 {
+            return Err("no frames were recorded".into());
+        }
+        let frames = (1..=self.frames).map(|n| -> Result<Pixmap, Box<dyn Error>> {
+            Ok(Pixmap::load_png(self.frame_path(n))?)
+        });
+        let first = Pixmap::load_png(self.frame_path(1))?;
+        let (width, height) = (first.width(), first.height());
+        let max = u32::from(u16::MAX);
+        if format == Format::Gif && (width > max || height > max) {
+            return Err(format!("{}x{} frames are too big for a GIF", width, height).into());
+        }
+        let file = BufWriter::new(File::create(&self.out)?);
+        match format {
+            Format::Gif => {
+                let (width, height) = (width as u16, height as u16);
+                let mut encoder = gif::Encoder::new(file, width, height, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                let delay = (100.0 / fps).round().max(1.0) as u16;
+                for pixmap in frames {
+                    let mut rgba = to_rgba(&pixmap?);
+                    let mut frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+                    frame.delay = delay;
+                    encoder.write_frame(&frame)?;
+                }
+            }
+            Format::Apng => {
+                let mut encoder = png::Encoder::new(file, width, height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(self.frames, 0)?;
+                encoder.set_frame_delay(1, fps.round() as u16)?;
+                let mut writer = encoder.write_header()?;
+                for pixmap in frames {
+                    writer.write_image_data(&to_rgba(&pixmap?))?;
+                }
+                writer.finish()?;
+            }
+        }
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn frames_are_assembled_into_animations() {
+        let dir = temp_dir("recordings");
+        for format in [Format::Gif, Format::Apng] {
+            let out = dir.join(format!("{:?}.{}", format, format.extension()));
+            let mut recorder = Recorder::new(&out).unwrap();
+            let raster = Raster::new(16, 8).unwrap();
+            for _ in 0..3 {
+                recorder.add(&raster).unwrap();
+            }
+            assert_eq!(recorder.frames(), 3);
+            assert!(dir.join(format!("{:?}-frames/frame-00003.png", format)).exists());
+            let data = fs::read(recorder.finish(format, 60.0).unwrap()).unwrap();
+            match format {
+                Format::Gif => assert!(data.starts_with(b"GIF89a")),
+                Format::Apng => assert!(data.windows(4).any(|chunk| chunk == b"acTL")),
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn empty_recordings_are_errors() {
+        let dir = temp_dir("empty-recordings");
+        let out = dir.join("empty.gif");
+        assert!(Recorder::new(&out).unwrap().finish(Format::Gif, 60.0).is_err());
+        assert!(!out.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,715 @@
+//! The `Sketch` trait, and the window boilerplate shared by all sketches.
+
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use nannou::{image::RgbaImage, prelude::*};
+use nannou_egui::{egui, Egui};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    clock::{self, Clock, Timing},
+    exe_name, headless,
+    palette::{self, Palette},
+    preset,
+    preset::{Capture, Preset},
+    print,
+    raster::Raster,
+    record::{self, Recorder},
+    svg, Canvas, FrameInfo,
+};
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Seed for the sketch's random number generators.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Start from a preset file (TOML, or JSON if it ends in `.json`). `--seed` overrides the
+    /// preset's seed.
+    #[arg(long)]
+    pub preset: Option<PathBuf>,
+    /// Render with the CPU rasterizer instead of opening a window.
+    #[arg(long)]
+    pub headless: bool,
+    /// Where to write the headless render (defaults to `<bin>-<seed>-<params>.png`).
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+    /// Number of frames to simulate before saving.
+    #[arg(long, default_value_t = 1)]
+    pub frames: u64,
+    /// Also write the drawing as an SVG file to this path.
+    #[arg(long)]
+    pub svg: Option<PathBuf>,
+    /// Resolution used to give the SVG its physical size, and of `--paper` prints.
+    #[arg(long, default_value_t = crate::svg::DEFAULT_DPI, value_parser = positive)]
+    pub dpi: f32,
+    /// Render images at this many times the window size, e.g. for printing.
+    #[arg(long, default_value_t = 1.0, value_parser = positive)]
+    pub scale: f32,
+    /// Render images as large as fits this paper size at `--dpi`, overriding `--scale`.
+    #[arg(long, value_enum)]
+    pub paper: Option<print::Paper>,
+    /// Record an animation of this many frames, or of one loop if the sketch loops.
+    #[arg(long, value_name = "FRAMES")]
+    pub record: Option<Option<u64>>,
+    /// Advance the window's clock by exactly one frame per frame drawn, instead of following
+    /// the wall clock. Recordings and headless renders always do this.
+    #[arg(long)]
+    pub fixed_step: bool,
+    /// File format of recorded animations.
+    #[arg(long, value_enum, default_value_t = record::Format::Gif)]
+    pub format: record::Format,
+}
+
+impl Args {
+    /// Pixels per canvas unit requested with `--scale` or `--paper`.
+    pub fn print_scale<S: Sketch>(&self) -> f32 {
+        match self.paper {
+            Some(paper) => paper.fit_scale(S::SIZE, self.dpi),
+            None => self.scale,
+        }
+    }
+
+    /// How many frames `--record` asks for, if any.
+    pub fn record_frames<S: Sketch>(&self) -> Option<u64> {
+        self.record
+            .map(|n| n.or(S::LOOP_FRAMES).unwrap_or(self.frames))
+    }
+}
+
+/// Parses `--scale` and `--dpi`, which have to be positive.
+pub fn positive(arg: &str) -> Result<f32, String> {
+    match arg.parse::<f32>() {
+        Ok(x) if x.is_finite() && x > 0.0 => Ok(x),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// What happens to the previous frame's drawing at the start of a new frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// The frame is cleared to the background colour every frame.
+    Clear,
+    /// The frame is only cleared when the sketch (re)starts, and each frame draws on top of the
+    /// previous ones.
+    Accumulate,
+}
+
+/// A generative sketch, which can be run in a window or rendered offline.
+///
+/// Running a sketch with [`run`] gives it the standard keybindings:
+/// - `S`: save the current frame as a PNG, without the overlay and panels
+/// - `V`: save the current drawing as an SVG
+/// - `R`: reseed the sketch and restart it
+/// - `P`: load the next preset from `presets/<bin>/`
+/// - `H`: save a high resolution render (see `--scale` and `--paper`)
+/// - `F`: start or stop recording an animation
+/// - `Space`: pause or resume
+/// - `.` / `,`: step one frame forward / back, e.g. while paused
+/// - `Backspace`: restart the sketch
+///
+/// Sketches using randomness should only draw from generators seeded with the value given to
+/// `reseed`, so that any output can be reproduced with `--seed`.
+pub trait Sketch: Sized + 'static {
+    /// Size of the window, and of offline renders.
+    const SIZE: [u32; 2];
+    const MODE: Mode = Mode::Clear;
+    const BACKGROUND: Srgb<u8> = FLORALWHITE;
+    /// Number of frames after which the animation repeats exactly, if it does. Recordings are
+    /// this long by default.
+    const LOOP_FRAMES: Option<u64> = None;
+
+    /// The tunable parameters saved in presets, apart from the seed.
+    type Params: Serialize + DeserializeOwned;
+
+    fn new() -> Self;
+
+    fn params(&self) -> Self::Params;
+
+    /// Switch to the given parameters. The sketch is restarted afterwards.
+    fn set_params(&mut self, params: Self::Params);
+
+    /// Only redraw in response to events, for sketches that don't animate.
+    fn loop_mode() -> LoopMode {
+        LoopMode::default()
+    }
+
+    /// Advance the state of the sketch by one frame.
+    fn update(&mut self, _info: &FrameInfo) {}
+
+    /// Draw the current frame. The background has already been taken care of according to
+    /// `MODE`.
+    fn draw(&self, canvas: &Canvas, info: &FrameInfo);
+
+    /// Draw on top of the current frame without becoming part of the drawing, e.g. to show
+    /// what the sketch is doing. Only shown in the window, never saved or recorded.
+    fn draw_overlay(&self, _canvas: &Canvas, _info: &FrameInfo) {}
+
+    /// Draw the whole drawing at once for vector export. Sketches that accumulate their drawing
+    /// over several frames should override this.
+    fn draw_vector(&self, canvas: &Canvas, info: &FrameInfo) {
+        self.draw(canvas, info);
+    }
+
+    /// Handle sketch-specific keys. Returns whether the drawing needs to restart from a blank
+    /// background.
+    fn key_pressed(&mut self, _key: Key) -> bool {
+        false
+    }
+
+    /// The seed the sketch's current state was generated from.
+    fn seed(&self) -> u64 {
+        0
+    }
+
+    /// Regenerate all random values of the sketch from `seed`.
+    fn reseed(&mut self, _seed: u64) {}
+
+    /// Use `seed` from now on without changing the parameters, e.g. when loading a preset.
+    /// Sketches whose parameters are random values of their own should override this.
+    fn set_seed(&mut self, seed: u64) {
+        self.reseed(seed);
+    }
+
+    /// A short description of the current parameters, used to name saved files.
+    fn summary(&self) -> String {
+        String::new()
+    }
+
+    /// Base name for files saved from the current state: `<bin>-<seed>-<summary>`.
+    fn file_stem(&self) -> String {
+        let params = self.summary();
+        if params.is_empty() {
+            format!("{}-{}", exe_name(), self.seed())
+        } else {
+            format!("{}-{}-{}", exe_name(), self.seed(), params)
+        }
+    }
+
+    /// The palette the sketch draws with, for sketches that can switch palettes.
+    fn palette(&self) -> Option<&Palette> {
+        None
+    }
+
+    /// Switch to another palette. The sketch is restarted afterwards.
+    fn set_palette(&mut self, _palette: Palette) {}
+
+    /// Called when the sketch restarts, e.g. after a resize.
+    fn reset(&mut self) {}
+
+    /// Add any egui controls the sketch needs. Returns whether the drawing needs to restart
+    /// from a blank background.
+    fn ui(&mut self, _ctx: &egui::Context) -> bool {
+        false
+    }
+}
+
+/// Run the sketch in a window, or offline if requested on the command line.
+pub fn run<S: Sketch>() {
+    let args = Args::parse();
+    if args.headless {
+        if let Err(e) = headless::run::<S>(&args) {
+            println!("Couldn't render: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    nannou::app(model::<S>).update(update::<S>).run();
+}
+
+/// Create the sketch, configured from the command line if requested.
+pub fn new_sketch<S: Sketch>(args: &Args) -> S {
+    let mut sketch = S::new();
+    if let Some(path) = &args.preset {
+        match Preset::load(path) {
+            Ok(preset) => preset.apply(&mut sketch),
+            Err(e) => {
+                println!("Couldn't load {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    match args.seed {
+        // Keep the preset's parameters, only changing the seed
+        Some(seed) if args.preset.is_some() => sketch.set_seed(seed),
+        Some(seed) => sketch.reseed(seed),
+        None => {}
+    }
+    sketch.reset();
+    sketch
+}
+
+/// A path for a new capture of the sketch, `<file_stem>.<ext>`, with a number appended if that
+/// (or its sidecar) would overwrite an earlier capture.
+pub fn capture_path<S: Sketch>(sketch: &S, ext: &str) -> PathBuf {
+    let stem = sketch.file_stem();
+    let mut path = PathBuf::from(format!("{}.{}", stem, ext));
+    let mut n = 1;
+    while path.exists() || path.with_extension("toml").exists() {
+        n += 1;
+        path = PathBuf::from(format!("{}-{}.{}", stem, n, ext));
+    }
+    path
+}
+
+/// Save the sketch's preset, along with how the capture was made, next to a capture: e.g.
+/// `foo.toml` for `foo.png`.
+pub fn save_sidecar<S: Sketch>(
+    sketch: &S,
+    capture: &Path,
+    info: &FrameInfo,
+    scale: f32,
+) -> Result<(), Box<dyn Error>> {
+    let mut preset = Preset::of(sketch);
+    preset.capture = Some(Capture::new(info, scale));
+    preset.save(capture.with_extension("toml"))
+}
+
+/// Save a capture's sidecar from the window, where failures are only reported.
+fn report_sidecar<S: Sketch>(sketch: &S, capture: &Path, info: &FrameInfo, scale: f32) {
+    if let Err(e) = save_sidecar(sketch, capture, info, scale) {
+        let path = capture.with_extension("toml");
+        println!("Couldn't save {}: {}", path.display(), e);
+    }
+}
+
+/// A fresh seed, kept small enough to be typed back in.
+pub fn random_seed() -> u64 {
+    random_range(0, 1_000_000)
+}
+
+/// An animation being recorded from the window.
+struct Recording {
+    recorder: Recorder,
+    /// The window's drawing, re-rendered on the CPU.
+    raster: Raster,
+    /// Stop automatically after this many frames.
+    limit: Option<u64>,
+    /// The window's timing before recording switched it to fixed steps.
+    timing: Timing,
+}
+
+impl Recording {
+    fn start<S: Sketch>(
+        sketch: &S,
+        rect: Rect,
+        limit: Option<u64>,
+        format: record::Format,
+        timing: Timing,
+    ) -> Result<Self, Box<dyn Error>> {
+        let path = capture_path(sketch, format.extension());
+        let recorder = Recorder::new(&path)?;
+        println!("Recording {}", path.display());
+        Ok(Recording {
+            recorder,
+            raster: Raster::new(rect.w() as u32, rect.h() as u32)?,
+            limit,
+            timing,
+        })
+    }
+
+    fn done(&self) -> bool {
+        self.limit
+            .is_some_and(|limit| self.recorder.frames() as u64 >= limit)
+    }
+}
+
+struct Model<S> {
+    args: Args,
+    sketch: S,
+    ui: Egui,
+    clock: Clock,
+    /// The last frame drawn to the window.
+    drawn: Cell<u64>,
+    /// The seed currently shown in the window title.
+    shown_seed: u64,
+    /// Index of the next preset to load with `P`.
+    next_preset: usize,
+    /// Filled in by `view`, hence the `RefCell`.
+    recording: RefCell<Option<Recording>>,
+    /// The drawing of an accumulating sketch, re-rendered on the CPU so that overlays can be
+    /// shown over it and taken away again.
+    mirror: RefCell<Option<Raster>>,
+    /// Whether the window shows an overlay, which has to be drawn over when it goes away.
+    overlaid: Cell<bool>,
+}
+
+impl<S: Sketch> Model<S> {
+    fn restart(&mut self) {
+        self.clock.restart();
+        self.drawn.set(0);
+        self.sketch.reset();
+    }
+
+    /// Advance the sketch by one frame.
+    fn step(&mut self, app: &App) {
+        self.clock.step();
+        self.sketch.update(&self.frame_info(app));
+    }
+
+    /// Go back one frame by replaying the sketch from the start. Accumulated drawings can't be
+    /// replayed this way, so this only works for sketches that clear every frame.
+    fn step_back(&mut self, app: &App) {
+        if S::MODE == Mode::Accumulate {
+            println!("Can't step back in a sketch that accumulates its drawing");
+            return;
+        }
+        let frame = self.clock.frame().saturating_sub(1);
+        let timing = self.clock.timing();
+        // Replay with fixed steps, as the real time each frame took is gone.
+        self.clock.set_timing(Timing::Fixed);
+        self.restart();
+        while self.clock.frame() < frame {
+            self.step(app);
+        }
+        self.clock.set_timing(timing);
+    }
+
+    /// Start recording, or finish the current recording.
+    fn toggle_recording(&mut self, app: &App) {
+        if let Some(recording) = self.recording.take() {
+            self.finish_recording(app, recording);
+        } else {
+            self.start_recording(app, S::LOOP_FRAMES);
+        }
+    }
+
+    fn start_recording(&mut self, app: &App, limit: Option<u64>) {
+        let recording = Recording::start(
+            &self.sketch,
+            app.window_rect(),
+            limit,
+            self.args.format,
+            self.clock.timing(),
+        );
+        let recording = match recording {
+            Ok(recording) => recording,
+            Err(e) => {
+                println!("Couldn't start recording: {}", e);
+                return;
+            }
+        };
+        self.recording.replace(Some(recording));
+        // Recordings play back at a fixed frame rate, so they have to be made with one.
+        self.clock.set_timing(Timing::Fixed);
+        // The recording has to start from a blank background.
+        if S::MODE == Mode::Accumulate {
+            self.restart();
+        }
+    }
+
+    fn finish_recording(&mut self, app: &App, recording: Recording) {
+        self.clock.set_timing(recording.timing);
+        match recording.recorder.finish(self.args.format, clock::FPS) {
+            Ok(path) => {
+                report_sidecar(&self.sketch, &path, &self.frame_info(app), 1.0);
+                println!("Saved {}", path.display());
+            }
+            Err(e) => println!("Couldn't save the recording: {}", e),
+        }
+    }
+
+    /// Render the current configuration offscreen at print resolution, by replaying it from the
+    /// start in a fresh copy of the sketch.
+    fn export_print(&self) {
+        // Without `--scale` or `--paper`, default to something big enough to be worth printing.
+        let scale = if self.args.paper.is_none() && self.args.scale == 1.0 {
+            4.0
+        } else {
+            self.args.print_scale::<S>()
+        };
+        let mut sketch = S::new();
+        Preset::of(&self.sketch).apply(&mut sketch);
+        sketch.reset();
+        let [width, height] = S::SIZE;
+        let mut raster = match Raster::scaled(width, height, scale) {
+            Ok(raster) => raster,
+            Err(e) => return println!("Couldn't render at scale {}: {}", scale, e),
+        };
+        println!("Rendering {}x{}...", raster.width(), raster.height());
+        let frames = self.clock.frame().max(1);
+        let info = headless::simulate(&mut sketch, frames, &mut raster, None).unwrap();
+        let path = capture_path(&sketch, "png");
+        if let Err(e) = self.save_print(&raster, &path) {
+            return println!("Couldn't save {}: {}", path.display(), e);
+        }
+        report_sidecar(&sketch, &path, &info, scale);
+        println!("Saved {}", path.display());
+    }
+
+    fn save_print(&self, raster: &Raster, path: &Path) -> Result<(), Box<dyn Error>> {
+        match self.args.paper {
+            Some(_) => raster.save_png_at_dpi(path, self.args.dpi),
+            None => raster.save_png(path),
+        }
+    }
+
+    /// Keep the mirror of an accumulating sketch's drawing up to date. Returns what to show
+    /// under the overlay: the new shapes, drawn on top of the previous frames, or while there
+    /// is or just was an overlay, the whole drawing from the mirror.
+    fn accumulate(
+        &self,
+        canvas: &Canvas,
+        overlay: &Canvas,
+        info: &FrameInfo,
+        new_frame: bool,
+    ) -> Canvas {
+        let rect = info.rect;
+        let (width, height) = (rect.w() as u32, rect.h() as u32);
+        let size = |n: u32| (n as f32 * info.scale).round() as u32;
+        let mut mirror = self.mirror.borrow_mut();
+        if mirror
+            .as_ref()
+            .is_none_or(|r| r.width() != size(width) || r.height() != size(height))
+        {
+            let Ok(mut raster) = Raster::scaled(width, height, info.scale) else {
+                // Nothing to mirror in a minimized window.
+                return canvas.clone();
+            };
+            let background = Canvas::new();
+            background.background(S::BACKGROUND);
+            raster.render(&background);
+            *mirror = Some(raster);
+        }
+        let raster = mirror.as_mut().unwrap();
+        if new_frame {
+            raster.render(canvas);
+        }
+        let overlaid = !overlay.items().is_empty();
+        if !overlaid && !self.overlaid.replace(overlaid) {
+            return canvas.clone();
+        }
+        let shown = Canvas::new();
+        let image = RgbaImage::from_raw(raster.width(), raster.height(), raster.rgba()).unwrap();
+        shown.image(rect, image);
+        shown
+    }
+
+    /// Save the sketch's drawing at the window's resolution, without the overlay or the UI.
+    /// Accumulating sketches can't be redrawn in one go, so their mirror is saved instead.
+    fn save_frame(&self, info: &FrameInfo, path: &Path) -> Result<(), Box<dyn Error>> {
+        match S::MODE {
+            Mode::Accumulate => match self.mirror.borrow().as_ref() {
+                Some(raster) => raster.save_png(path),
+                None => Err("nothing has been drawn yet".into()),
+            },
+            Mode::Clear => {
+                let rect = info.rect;
+                let mut raster = Raster::scaled(rect.w() as u32, rect.h() as u32, info.scale)?;
+                let canvas = Canvas::new();
+                canvas.background(S::BACKGROUND);
+                self.sketch.draw(&canvas, info);
+                raster.render(&canvas);
+                raster.save_png(path)
+            }
+        }
+    }
+
+    fn frame_info(&self, app: &App) -> FrameInfo {
+        self.clock
+            .info(app.window_rect(), app.main_window().scale_factor())
+    }
+}
+
+fn title<S: Sketch>(sketch: &S) -> String {
+    format!("{} (seed {})", exe_name(), sketch.seed())
+}
+
+fn model<S: Sketch>(app: &App) -> Model<S> {
+    app.set_loop_mode(S::loop_mode());
+    let args = Args::parse();
+    let sketch: S = new_sketch(&args);
+    println!("Seed: {}", sketch.seed());
+    let [width, height] = S::SIZE;
+    let window_id = app
+        .new_window()
+        .title(title(&sketch))
+        .size(width, height)
+        .view(view::<S>)
+        .key_pressed(key_pressed::<S>)
+        .resized(resized::<S>)
+        .raw_event(raw_window_event::<S>)
+        .build()
+        .unwrap();
+    let window = app.window(window_id).unwrap();
+
+    let timing = if args.fixed_step {
+        Timing::Fixed
+    } else {
+        Timing::RealTime
+    };
+    let mut model = Model {
+        shown_seed: sketch.seed(),
+        sketch,
+        ui: Egui::from_window(&window),
+        clock: Clock::new(timing),
+        drawn: Cell::new(0),
+        next_preset: 0,
+        recording: RefCell::new(None),
+        mirror: RefCell::new(None),
+        overlaid: Cell::new(false),
+        args,
+    };
+    // Without a frame count, record until `F` is pressed.
+    if let Some(frames) = model.args.record {
+        model.start_recording(app, frames.or(S::LOOP_FRAMES));
+    }
+    model
+}
+
+fn update<S: Sketch>(app: &App, model: &mut Model<S>, update: Update) {
+    model.ui.set_elapsed_time(update.since_start);
+    let ctx = model.ui.begin_frame();
+    let restart = model.sketch.ui(&ctx);
+    drop(ctx);
+    if restart {
+        model.restart();
+    }
+
+    if model.sketch.seed() != model.shown_seed {
+        model.shown_seed = model.sketch.seed();
+        app.main_window().set_title(&title(&model.sketch));
+        println!("Seed: {}", model.shown_seed);
+    }
+
+    let done = model
+        .recording
+        .borrow()
+        .as_ref()
+        .is_some_and(Recording::done);
+    if done {
+        let recording = model.recording.take().unwrap();
+        model.finish_recording(app, recording);
+    }
+
+    if model.clock.tick(update.since_last.as_secs_f32()) {
+        let info = model.frame_info(app);
+        model.sketch.update(&info);
+    }
+}
+
+fn key_pressed<S: Sketch>(app: &App, model: &mut Model<S>, key: Key) {
+    match key {
+        Key::S => {
+            let info = model.frame_info(app);
+            let path = capture_path(&model.sketch, "png");
+            match model.save_frame(&info, &path) {
+                Ok(()) => {
+                    report_sidecar(&model.sketch, &path, &info, info.scale);
+                    println!("Saved {}", path.display());
+                }
+                Err(e) => println!("Couldn't save {}: {}", path.display(), e),
+            }
+        }
+        Key::V => {
+            let info = model.frame_info(app);
+            let canvas = Canvas::new();
+            canvas.background(S::BACKGROUND);
+            model.sketch.draw_vector(&canvas, &info);
+            let path = capture_path(&model.sketch, "svg");
+            match svg::save(&canvas, app.window_rect(), &path, model.args.dpi) {
+                Ok(()) => {
+                    report_sidecar(&model.sketch, &path, &info, 1.0);
+                    println!("Saved {}", path.display());
+                }
+                Err(e) => println!("Couldn't save {}: {}", path.display(), e),
+            }
+        }
+        Key::P => {
+            let presets = preset::list();
+            if presets.is_empty() {
+                println!("No presets in {}", preset::dir().display());
+                return;
+            }
+            let path = &presets[model.next_preset % presets.len()];
+            model.next_preset = (model.next_preset + 1) % presets.len();
+            match Preset::load(path) {
+                Ok(preset) => {
+                    println!("Loaded {}", path.display());
+                    preset.apply(&mut model.sketch);
+                    model.restart();
+                }
+                Err(e) => println!("Couldn't load {}: {}", path.display(), e),
+            }
+        }
+        Key::K => {
+            let Some(current) = model.sketch.palette() else {
+                return;
+            };
+            let palettes = palette::list();
+            let next = palettes
+                .iter()
+                .position(|p| p.name() == current.name())
+                .map_or(0, |i| (i + 1) % palettes.len());
+            println!("Palette {}", palettes[next].name());
+            model.sketch.set_palette(palettes[next].clone());
+            model.restart();
+        }
+        Key::R => {
+            model.sketch.reseed(random_seed());
+            model.restart();
+        }
+        Key::H => model.export_print(),
+        Key::F => model.toggle_recording(app),
+        Key::Space => model.clock.toggle_pause(),
+        Key::Period => model.step(app),
+        Key::Comma => model.step_back(app),
+        Key::Back => model.restart(),
+        _ => {
+            if model.sketch.key_pressed(key) {
+                model.restart();
+            }
+        }
+    }
+}
+
+fn resized<S: Sketch>(_app: &App, model: &mut Model<S>, _: Vec2) {
+    model.restart();
+}
+
+fn raw_window_event<S: Sketch>(
+    _app: &App,
+    model: &mut Model<S>,
+    event: &nannou::winit::event::WindowEvent,
+) {
+    model.ui.handle_raw_event(event);
+}
+
+fn view<S: Sketch>(app: &App, model: &Model<S>, frame: Frame) {
+    // Drawing the same frame twice would darken accumulating sketches, e.g. while paused.
+    let new_frame = model.drawn.replace(model.clock.frame()) != model.clock.frame();
+    let info = model.frame_info(app);
+    let canvas = Canvas::new();
+    if S::MODE == Mode::Clear || model.clock.frame() <= 1 {
+        canvas.background(S::BACKGROUND);
+    }
+    if S::MODE == Mode::Clear || new_frame {
+        model.sketch.draw(&canvas, &info);
+    }
+    let overlay = Canvas::new();
+    model.sketch.draw_overlay(&overlay, &info);
+    // Overlays can't be drawn straight into the frame of an accumulating sketch, as they would
+    // stay there.
+    let shown = match S::MODE {
+        Mode::Clear => canvas.clone(),
+        Mode::Accumulate => model.accumulate(&canvas, &overlay, &info, new_frame),
+    };
+    let draw = app.draw();
+    shown.to_draw(app, &draw);
+    overlay.to_draw(app, &draw);
+    draw.to_frame(app, &frame).unwrap();
+    if let Some(recording) = model.recording.borrow_mut().as_mut() {
+        if new_frame {
+            recording.raster.render(&canvas);
+            if let Err(e) = recording.recorder.add(&recording.raster) {
+                println!("Couldn't save frame {}: {}", recording.recorder.frames(), e);
+            }
+        }
+    }
+    model.ui.draw_to_frame(&frame).unwrap();
+}
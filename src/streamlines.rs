@@ -0,0 +1,232 @@
+//! Placing flow lines so they never come too close to each other, after Jobard and Lefer,
+//! "Creating evenly-spaced streamlines of arbitrary density" (1997).
+
+use std::{collections::VecDeque, error::Error};
+
+use nannou::prelude::*;
+
+use crate::{
+    flow::{FlowField, Integrator},
+    grid::Grid,
+};
+
+/// Most steps a line can take in each direction, in case it gets caught somewhere.
+const MAX_STEPS: usize = 100_000;
+/// Most lines side by side across the field, which keeps the occupancy grid to a million cells.
+const MAX_LINES_ACROSS: f32 = 1000.0;
+
+/// The points of the lines placed so far, for checking how close new points come to them.
+pub struct Occupancy {
+    grid: Grid,
+    /// Each point with its line and how far along the line it is.
+    points: Vec<(Vec2, usize, f32)>,
+}
+
+impl Occupancy {
+    /// Queries are fastest for distances up to about `cell_size`.
+    pub fn new(bounds: Rect, cell_size: f32) -> Self {
+        Occupancy {
+            grid: Grid::new(bounds, cell_size),
+            points: vec![],
+        }
+    }
+
+    /// Add point `p` of `line`, `s` along it.
+    pub fn insert(&mut self, p: Vec2, line: usize, s: f32) {
+        self.grid
+            .insert(self.points.len(), Rect::from_xy_wh(p, Vec2::ZERO));
+        self.points.push((p, line, s));
+    }
+
+    /// Whether no point lies within `d` of `p`. A line being traced passes itself as `own`,
+    /// with how far along it `p` is, so that only points of its own further away along it
+    /// than `2 * d` count.
+    pub fn is_free(&self, p: Vec2, d: f32, own: Option<(usize, f32)>) -> bool {
+        let d2 = d * d;
+        self.grid
+            .query(Rect::from_xy_wh(p, Vec2::splat(2.0 * d)))
+            .all(|i| {
+                let (q, line, s) = self.points[i];
+                let neighbour = own.is_some_and(|(l, at)| l == line && (at - s).abs() <= 2.0 * d);
+                neighbour || q.distance_squared(p) >= d2
+            })
+    }
+}
+
+/// How evenly spaced lines are placed.
+#[derive(Clone, Copy, Debug)]
+pub struct Spacing {
+    /// Distance between neighbouring lines.
+    pub separation: f32,
+    /// Lines end when they come closer than this fraction of `separation` to another line.
+    pub test: f32,
+    pub step: f32,
+    pub integrator: Integrator,
+}
+
+impl Spacing {
+    /// Fill the field with lines, starting from a line through `start`. New lines start at
+    /// `separation` beside existing ones, and where that doesn't reach, anywhere still free.
+    /// The separation and step have to be above 0, and the separation at least a thousandth
+    /// of the field's longer side.
+    pub fn place(&self, field: &FlowField, start: Vec2) -> Result<Vec<Vec<Vec2>>, Box<dyn Error>> {
+        for (name, value) in [("separation", self.separation), ("step", self.step)] {
+            if value.is_nan() || value <= 0.0 {
+                return Err(format!("{} has to be above 0, got {}", name, value).into());
+            }
+        }
+        let rect = field.rect();
+        let min_separation = rect.w().max(rect.h()) / MAX_LINES_ACROSS;
+        if self.separation < min_separation {
+            return Err(format!(
+                "separation has to be at least {} for a {}x{} field, got {}",
+                min_separation,
+                rect.w(),
+                rect.h(),
+                self.separation
+            )
+            .into());
+        }
+        let mut occupancy = Occupancy::new(rect, self.separation);
+        let mut lines: Vec<Vec<Vec2>> = vec![];
+        let cols = (rect.w() / self.separation) as usize;
+        let rows = (rect.h() / self.separation) as usize;
+        let fallback = (0..rows).flat_map(|row| {
+            (0..cols).map(move |col| {
+                rect.bottom_left() + (vec2(col as f32, row as f32) + 0.5) * self.separation
+            })
+        });
+
+        for seed in std::iter::once(start).chain(fallback) {
+            let mut queue = VecDeque::from([seed]);
+            while let Some(seed) = queue.pop_front() {
+                if !rect.contains(seed) || !occupancy.is_free(seed, self.separation * 0.99, None) {
+                    continue;
+                }
+                let line = self.trace(field, &mut occupancy, seed, lines.len());
+                if line.len() < 2 {
+                    continue;
+                }
+                // Candidate seeds on both sides of every point
+                for (i, p) in line.iter().enumerate() {
+                    let next = line[(i + 1).min(line.len() - 1)];
+                    let prev = line[i.saturating_sub(1)];
+                    let normal = (next - prev).perp().normalize_or_zero() * self.separation;
+                    queue.push_back(*p + normal);
+                    queue.push_back(*p - normal);
+                }
+                lines.push(line);
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Follow the field both ways from `seed` until the line comes too close to a line or
+    /// itself, leaves the field or the field vanishes. Only lines that get beyond their seed
+    /// leave points in `occupancy`.
+    fn trace(
+        &self,
+        field: &FlowField,
+        occupancy: &mut Occupancy,
+        seed: Vec2,
+        line: usize,
+    ) -> Vec<Vec2> {
+        let d_test = self.separation * self.test;
+        let mut half = |direction: f32| {
+            let mut pts = vec![];
+            let (mut p, mut s) = (seed, 0.0);
+            for _ in 0..MAX_STEPS {
+                let Some(next) = field.step(p, self.step * direction, self.integrator) else {
+                    break;
+                };
+                s += self.step * direction;
+                if !field.rect().contains(next) || !occupancy.is_free(next, d_test, Some((line, s)))
+                {
+                    break;
+                }
+                occupancy.insert(next, line, s);
+                pts.push(next);
+                p = next;
+            }
+            pts
+        };
+        let forward = half(1.0);
+        let mut pts = half(-1.0);
+        if !forward.is_empty() || !pts.is_empty() {
+            occupancy.insert(seed, line, 0.0);
+        }
+        pts.reverse();
+        pts.push(seed);
+        pts.extend(forward);
+        pts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform(v: Vec2) -> FlowField {
+        FlowField::from_fn(Rect::from_w_h(100.0, 100.0), 5, 5, |_| v)
+    }
+
+    fn spacing(separation: f32) -> Spacing {
+        Spacing {
+            separation,
+            test: 0.5,
+            step: 1.0,
+            integrator: Integrator::Rk4,
+        }
+    }
+
+    #[test]
+    fn lines_keep_their_distance() {
+        let lines = spacing(10.0)
+            .place(&uniform(vec2(1.0, 0.0)), Vec2::ZERO)
+            .unwrap();
+        assert!(lines.len() >= 9, "only {} lines", lines.len());
+        for (i, a) in lines.iter().enumerate() {
+            // Straight across the field
+            assert!(a.iter().all(|p| (p.y - a[0].y).abs() < 1e-3));
+            for b in &lines[i + 1..] {
+                assert!((a[0].y - b[0].y).abs() >= 5.0);
+            }
+        }
+    }
+
+    #[test]
+    fn rejected_seeds_leave_no_points() {
+        let spacing = spacing(10.0);
+        let field = uniform(Vec2::ZERO);
+        let mut occupancy = Occupancy::new(field.rect(), 10.0);
+        let line = spacing.trace(&field, &mut occupancy, Vec2::ZERO, 0);
+        assert_eq!(line, [Vec2::ZERO]);
+        assert!(occupancy.points.is_empty());
+        assert!(spacing.place(&field, Vec2::ZERO).unwrap().is_empty());
+    }
+
+    #[test]
+    fn occupancy_ignores_nearby_points_of_the_same_line() {
+        let mut occupancy = Occupancy::new(Rect::from_w_h(100.0, 100.0), 10.0);
+        occupancy.insert(Vec2::ZERO, 0, 0.0);
+        assert!(!occupancy.is_free(vec2(1.0, 0.0), 2.0, None));
+        assert!(!occupancy.is_free(vec2(1.0, 0.0), 2.0, Some((1, 1.0))));
+        assert!(occupancy.is_free(vec2(1.0, 0.0), 2.0, Some((0, 1.0))));
+        assert!(!occupancy.is_free(vec2(1.0, 0.0), 2.0, Some((0, 10.0))));
+        assert!(occupancy.is_free(vec2(3.0, 0.0), 2.0, None));
+    }
+
+    #[test]
+    fn spacing_has_to_be_positive() {
+        let field = uniform(vec2(1.0, 0.0));
+        assert!(spacing(0.0).place(&field, Vec2::ZERO).is_err());
+        assert!(spacing(f32::NAN).place(&field, Vec2::ZERO).is_err());
+        // Would need a grid of ten billion cells
+        assert!(spacing(0.001).place(&field, Vec2::ZERO).is_err());
+        let still = Spacing {
+            step: 0.0,
+            ..spacing(10.0)
+        };
+        assert!(still.place(&field, Vec2::ZERO).is_err());
+    }
+}
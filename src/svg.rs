@@ -0,0 +1,262 @@
+//! SVG export of `Canvas` drawings, e.g. for sending to a pen plotter.
+
+use std::{fmt::Write, fs, io, path::Path};
+
+use nannou::{color::LinSrgba, image::RgbaImage, prelude::*};
+
+use crate::canvas::{self, Canvas, Cap, Join, Shape, Stroke};
+
+/// The CSS reference resolution, i.e. one canvas unit is one CSS pixel.
+pub const DEFAULT_DPI: f32 = 96.0;
+
+/// A vector document that `Canvas` drawings can be rendered into.
+///
+/// Like `Raster`, drawings accumulate across renders until a background is drawn.
+pub struct Svg {
+    width: u32,
+    height: u32,
+    elements: Vec<String>,
+}
+
+impl Svg {
+    pub fn new(width: u32, height: u32) -> Self {
+        Svg {
+            width,
+            height,
+            elements: vec![],
+        }
+    }
+
+    /// Maps nannou's coordinates (origin in the middle, y pointing up) to SVG user units.
+    fn base_transform(&self) -> Affine2 {
+        Affine2::from_translation(vec2(self.width as f32, self.height as f32) / 2.0)
+            * Affine2::from_scale(vec2(1.0, -1.0))
+    }
+
+    pub fn render(&mut self, canvas: &Canvas) {
+        let base = self.base_transform();
+        for item in canvas.items().iter() {
+            let t = base * item.transform;
+            let el = match &item.shape {
+                Shape::Background(color) => {
+                    // Nothing drawn before a background would be visible anyway.
+                    self.elements.clear();
+                    format!(
+                        r#"<rect width="100%" height="100%" stroke="none" {}/>"#,
+                        paint("fill", *color)
+                    )
+                }
+                Shape::Polyline { points, stroke } => {
+                    let mut d = String::new();
+                    for (i, p) in points.iter().enumerate() {
+                        let cmd = if i == 0 { 'M' } else { 'L' };
+                        write!(d, "{}{} {}", cmd, p.x, p.y).unwrap();
+                    }
+                    format!(
+                        r#"<path transform="{}" d="{}" fill="none" {}/>"#,
+                        matrix(t),
+                        d,
+                        stroke_attrs(stroke)
+                    )
+                }
+                Shape::Polygon { points, color } => format!(
+                    r#"<polygon transform="{}" points="{}" stroke="none" {}/>"#,
+                    matrix(t),
+                    points
+                        .iter()
+                        .map(|p| format!("{},{}", p.x, p.y))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    paint("fill", *color)
+                ),
+                Shape::Ellipse {
+                    center,
+                    size,
+                    color,
+                } => format!(
+                    r#"<ellipse transform="{}" cx="{}" cy="{}" rx="{}" ry="{}" stroke="none" {}/>"#,
+                    matrix(t),
+                    center.x,
+                    center.y,
+                    size.x / 2.0,
+                    size.y / 2.0,
+                    paint("fill", *color)
+                ),
+                Shape::Rect {
+                    center,
+                    size,
+                    rotation,
+                    stroke,
+                } => {
+                    let points = canvas::rect_corners(*center, *size, *rotation)
+                        .iter()
+                        .map(|p| format!("{},{}", p.x, p.y))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    format!(
+                        r#"<polygon transform="{}" points="{}" fill="none" {}/>"#,
+                        matrix(t),
+                        points,
+                        stroke_attrs(stroke)
+                    )
+                }
+                Shape::Text {
+                    text,
+                    position,
+                    size,
+                    color,
+                } => {
+                    let p = t.transform_point2(*position);
+                    format!(
+                        r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" dominant-baseline="middle" {}>{}</text>"#,
+                        p.x,
+                        p.y,
+                        size,
+                        paint("fill", *color),
+                        escape(text)
+                    )
+                }
+                Shape::Image { rect, image } => format!(
+                    r#"<image transform="{}" width="{}" height="{}" preserveAspectRatio="none" href="data:image/png;base64,{}"/>"#,
+                    matrix(t * canvas::image_transform(*rect, image)),
+                    image.width(),
+                    image.height(),
+                    base64(&encode_png(image))
+                ),
+            };
+            self.elements.push(el);
+        }
+    }
+
+    /// The SVG document, with its physical size derived from `dpi`.
+    pub fn document(&self, dpi: f32) -> String {
+        let mm = |px: u32| px as f32 / dpi * 25.4;
+        let mut doc = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}mm" height="{}mm" viewBox="0 0 {} {}">"#,
+            mm(self.width),
+            mm(self.height),
+            self.width,
+            self.height
+        );
+        doc.push('\n');
+        for el in &self.elements {
+            doc.push_str(el);
+            doc.push('\n');
+        }
+        doc.push_str("</svg>\n");
+        doc
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, dpi: f32) -> io::Result<()> {
+        fs::write(path, self.document(dpi))
+    }
+}
+
+/// Render a single canvas to an SVG file the size of `rect`, printed at `dpi`.
+pub fn save<P: AsRef<Path>>(canvas: &Canvas, rect: Rect, path: P, dpi: f32) -> io::Result<()> {
+    let mut svg = Svg::new(rect.w() as u32, rect.h() as u32);
+    svg.render(canvas);
+    svg.save(path, dpi)
+}
+
+fn matrix(t: Affine2) -> String {
+    format!(
+        "matrix({} {} {} {} {} {})",
+        t.x_axis.x, t.x_axis.y, t.y_axis.x, t.y_axis.y, t.translation.x, t.translation.y
+    )
+}
+
+fn paint(attr: &str, color: LinSrgba) -> String {
+    let c = canvas::to_srgba(color);
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        r#"{attr}="rgb({},{},{})" {attr}-opacity="{}""#,
+        byte(c.red),
+        byte(c.green),
+        byte(c.blue),
+        c.alpha.clamp(0.0, 1.0)
+    )
+}
+
+fn stroke_attrs(stroke: &Stroke) -> String {
+    let cap = match stroke.cap {
+        Cap::Butt => "butt",
+        Cap::Round => "round",
+    };
+    let join = match stroke.join {
+        Join::Miter => "miter",
+        Join::Round => "round",
+    };
+    format!(
+        r#"{} stroke-width="{}" stroke-linecap="{}" stroke-linejoin="{}""#,
+        paint("stroke", stroke.color),
+        stroke.weight,
+        cap,
+        join
+    )
+}
+
+fn encode_png(image: &RgbaImage) -> Vec<u8> {
+    let mut data = vec![];
+    let mut encoder = png::Encoder::new(&mut data, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(image.as_raw()).unwrap();
+    writer.finish().unwrap();
+    data
+}
+
+fn base64(data: &[u8]) -> String {
+    const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(CHARS[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (data, encoded) in cases {
+            assert_eq!(base64(data.as_bytes()), encoded);
+        }
+    }
+
+    #[test]
+    fn base64_uses_the_whole_alphabet() {
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(base64(&[0, 0, 0]), "AAAA");
+    }
+}